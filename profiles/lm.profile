@@ -0,0 +1,9 @@
+# Luigi's Mansion.
+[profile:lm][entrysize:8][encoding:shift-jis]
+
+[attribute:unk0][offset:0][size:1]
+[attribute:unk1][offset:1][size:1]
+[attribute:unk2][offset:2][size:1]
+[attribute:unk3][offset:3][size:1]
+
+[escape:color][type:255][fields:u16=0,u8]
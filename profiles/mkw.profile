@@ -0,0 +1,12 @@
+# Mario Kart Wii. Escapes for font scale, colors and numbers filled in by
+# the game.
+[profile:mkw][entrysize:8][encoding:utf16][messageids]
+
+[attribute:unk0][offset:0][size:1]
+[attribute:unk1][offset:1][size:1]
+[attribute:unk2][offset:2][size:1]
+[attribute:unk3][offset:3][size:1]
+
+[escape:scale][type:0][fields:u16=0,u16]
+[escape:number][type:2][fields:u16,u32]
+[escape:color][type:255][fields:u16=0,u16]
@@ -0,0 +1,311 @@
+# Super Mario Galaxy.
+[profile:smg1][entrysize:12][encoding:utf16]
+
+[attribute:type][offset:4][size:1][table:messagetypes]
+[attribute:boxtype][offset:5][size:1][table:boxtypes]
+[attribute:sound][offset:2][size:1][table:sounds]
+[attribute:cam][offset:3][size:1][table:cameras]
+[attribute:cameraid][offset:0][size:2]
+[attribute:messageareaid][offset:6][size:1]
+[attribute:padding][offset:7][size:1]
+
+[escape:waittime][type:1][fields:u16=0,u8,u8=0]
+[escape:newline][type:1][fields:u16=1]
+[escape:center][type:1][fields:u16=3]
+[escape:sound][type:2][fields:u16=0,text]
+[escape:icon][type:3][fields:u16:icons]
+[escape:fontsize][type:4][fields:u16:fontsizes]
+[escape:playername][type:5][fields:u16=0,u8:playernames,u8=0]
+[escape:valint][type:6][fields:u16,u32,u32]
+[escape:valstr][type:7][fields:u16,u32,u32]
+[escape:color][type:255][fields:u16=0,u8:colors,u8=0]
+
+[condition:MultipleChoice][type:0][choice:choices]
+[condition:Coded][type:1]
+[condition:PlayerNearNpc][type:2]
+[condition:SwA][type:3]
+[condition:SwB][type:4]
+[condition:PlayerStateNoPowerUp][type:5]
+[condition:PlayerStateBee][type:6]
+[condition:PlayerStateBoo][type:7]
+[condition:PowerStarSpawned][type:8]
+[condition:AlreadyTalkedScene][type:9]
+[condition:PlayerLuigi][type:10]
+[condition:GetBranchAstroGalaxyResult][type:11]
+[condition:CutsceneActive][type:12]
+[condition:AlreadyTalkedSaved][type:13]
+[condition:IsMsgLedPattern][type:14]
+
+[event:NpcEvent][type:0]
+[event:NpcEventAlt][type:1]
+[event:NextText][type:2]
+[event:Unk][type:3]
+[event:Emotion][type:4]
+[event:SwA][type:5]
+[event:SwB][type:6]
+[event:Metamorphosis][type:7]
+
+[table:boxtypes][value:0][name:Normal]
+[table:boxtypes][value:4][name:SignBoard]
+
+[table:cameras][value:0][name:Normal]
+[table:cameras][value:1][name:CameraId]
+[table:cameras][value:2][name:NoCam]
+
+[table:choices][value:0][name:PenguinRace]
+[table:choices][value:1][name:SwimmingSchool]
+[table:choices][value:2][name:PenguinRaceAlt]
+[table:choices][value:3][name:BombTimeAttackLv1]
+[table:choices][value:4][name:PhantomTeresaRacer]
+[table:choices][value:5][name:BombTimeAttackLv2]
+[table:choices][value:6][name:TrialSurfingCoach]
+[table:choices][value:7][name:TrialSurfingHowTo]
+[table:choices][value:8][name:DeathPromenadeTeresaRacer]
+[table:choices][value:9][name:RosettaFinalBattle]
+[table:choices][value:10][name:CometTico]
+[table:choices][value:11][name:TransformTico]
+[table:choices][value:12][name:ChallengeSurfingCoach]
+[table:choices][value:13][name:TicoShopExchange]
+[table:choices][value:14][name:TicoShopWhich]
+[table:choices][value:15][name:KinopioPurple]
+[table:choices][value:16][name:CometTicoTell]
+[table:choices][value:17][name:TrialTamakoroHowTo]
+[table:choices][value:18][name:KnockOnTheDoor]
+[table:choices][value:19][name:LedPattern]
+
+[table:colors][value:0][name:black]
+[table:colors][value:1][name:red]
+[table:colors][value:2][name:green]
+[table:colors][value:3][name:blue]
+[table:colors][value:4][name:yellow]
+[table:colors][value:5][name:purple]
+[table:colors][value:6][name:lightred]
+[table:colors][value:7][name:lightgreen]
+[table:colors][value:8][name:lightblue]
+[table:colors][value:9][name:lightyellow]
+[table:colors][value:10][name:brightpink]
+[table:colors][value:11][name:gray]
+
+[table:fontsizes][value:0][name:small]
+[table:fontsizes][value:1][name:normal]
+[table:fontsizes][value:2][name:large]
+
+[table:icons][value:0][name:abutton]
+[table:icons][value:1][name:bbutton]
+[table:icons][value:2][name:cbutton]
+[table:icons][value:3][name:wiiremote]
+[table:icons][value:4][name:numchuck]
+[table:icons][value:5][name:onebutton]
+[table:icons][value:6][name:twobutton]
+[table:icons][value:7][name:star]
+[table:icons][value:8][name:launchstar]
+[table:icons][value:9][name:pullstar]
+[table:icons][value:10][name:pullstaricon]
+[table:icons][value:11][name:starbit]
+[table:icons][value:12][name:coconut]
+[table:icons][value:13][name:bell]
+[table:icons][value:14][name:starbunny]
+[table:icons][value:15][name:numchuckpad]
+[table:icons][value:16][name:xmark]
+[table:icons][value:17][name:coin]
+[table:icons][value:18][name:mario]
+[table:icons][value:19][name:dpad]
+[table:icons][value:20][name:pullstarchip]
+[table:icons][value:21][name:launchstarchip]
+[table:icons][value:22][name:homebutton]
+[table:icons][value:23][name:minusbutton]
+[table:icons][value:24][name:plusbutton]
+[table:icons][value:25][name:zbutton]
+[table:icons][value:26][name:silverstar]
+[table:icons][value:27][name:grandstar]
+[table:icons][value:28][name:luigi]
+[table:icons][value:29][name:launchstaricon]
+[table:icons][value:30][name:purplecoin]
+[table:icons][value:31][name:greenstar]
+[table:icons][value:32][name:crown]
+[table:icons][value:33][name:cannonpoint]
+[table:icons][value:34][name:space]
+[table:icons][value:35][name:redstar]
+[table:icons][value:36][name:handclosed]
+[table:icons][value:37][name:handpointer]
+[table:icons][value:38][name:handopen]
+[table:icons][value:39][name:rainbowstarbit]
+[table:icons][value:40][name:peach]
+[table:icons][value:41][name:mail]
+[table:icons][value:42][name:questionmark]
+[table:icons][value:43][name:marioorluigi]
+[table:icons][value:49][name:oneup]
+[table:icons][value:50][name:lifeshroom]
+[table:icons][value:51][name:hungryluma]
+[table:icons][value:52][name:polari]
+[table:icons][value:53][name:comet]
+[table:icons][value:54][name:greenquestionmark]
+
+[table:messagetypes][value:0][name:Talk]
+[table:messagetypes][value:1][name:Shout]
+[table:messagetypes][value:2][name:Auto]
+[table:messagetypes][value:3][name:Crash]
+[table:messagetypes][value:4][name:Empty]
+
+[table:playernames][value:0][name:normal]
+[table:playernames][value:1][name:formal]
+[table:playernames][value:2][name:moustache]
+
+[table:sounds][value:0][name:None]
+[table:sounds][value:1][name:SeSvKinopioTalkHey]
+[table:sounds][value:2][name:SeSvKinopioTalkYahoo]
+[table:sounds][value:3][name:SeSvKinopioTalkAngry]
+[table:sounds][value:4][name:SeSvKinopioTalkSad]
+[table:sounds][value:5][name:SeSvKinopioTalkHappy]
+[table:sounds][value:6][name:SeSvKinopioTalkSleep]
+[table:sounds][value:7][name:SeSvRabbitTalkNormal]
+[table:sounds][value:8][name:SeSvRabbitTalkCaught]
+[table:sounds][value:9][name:SeSvRabbitTalkThats]
+[table:sounds][value:10][name:SeSvRabbitTalkHelp]
+[table:sounds][value:11][name:SeSvRabbitTalkThanks]
+[table:sounds][value:12][name:SeSvPenguinLTalkNormal]
+[table:sounds][value:13][name:SeSvPenguinLTalkPleased]
+[table:sounds][value:14][name:SeSvPenguinLTalkNg]
+[table:sounds][value:15][name:SeSvPenguinLTalkQuestion]
+[table:sounds][value:16][name:SeSvPenguinLTalkDistant]
+[table:sounds][value:17][name:SeSvPenguinLTalkNormalL]
+[table:sounds][value:18][name:SeSvPenguinLTalkPleasedL]
+[table:sounds][value:19][name:SeSvPenguinLTalkNgL]
+[table:sounds][value:20][name:SeSvPenguinLTalkQuestionL]
+[table:sounds][value:21][name:SeSvPenguinLTalkOh]
+[table:sounds][value:22][name:SeSvPenguinSTalkNormal]
+[table:sounds][value:23][name:SeSvPenguinSTalkGlad]
+[table:sounds][value:24][name:SeSvPenguinSTalkGladHigh]
+[table:sounds][value:25][name:SeSvPenguinSTalkAngry]
+[table:sounds][value:26][name:SeSvPenguinSTalkSad]
+[table:sounds][value:27][name:SeSvPenguinSTalkHappy]
+[table:sounds][value:28][name:SeSvPenguinSTalkStrong]
+[table:sounds][value:29][name:SeSvPenguinSTalkNormalW]
+[table:sounds][value:30][name:SeSvPenguinSTalkGreet]
+[table:sounds][value:31][name:SeSvPenguinSTalkWin]
+[table:sounds][value:32][name:SeSvPenguinSTalkLose]
+[table:sounds][value:33][name:SeSvPenguinSTalkOuch]
+[table:sounds][value:34][name:SeSvPenguinAceTalkNormal]
+[table:sounds][value:35][name:SeSvPenguinAceTalkGreet]
+[table:sounds][value:36][name:SeSvPenguinAceTalkWin]
+[table:sounds][value:37][name:SeSvPenguinAceTalkLose]
+[table:sounds][value:38][name:SeSvSyatiTalkNormal]
+[table:sounds][value:39][name:SeSvSyatiTalkRingMeet]
+[table:sounds][value:40][name:SeSvSyatiTalkRingWin]
+[table:sounds][value:41][name:SeSvSyatiTalkRingLose]
+[table:sounds][value:42][name:SeSvSyatiTalkPhanMeet]
+[table:sounds][value:43][name:SeSvSyatiTalkPhanWin]
+[table:sounds][value:44][name:SeSvSyatiTalkPhanLose]
+[table:sounds][value:45][name:SeSvPenguinSsHappy]
+[table:sounds][value:46][name:SeSvPenguinSsGreet]
+[table:sounds][value:47][name:SeSvPenguinSsDamage]
+[table:sounds][value:48][name:SeSvPenguinSsDisappointed]
+[table:sounds][value:49][name:SeSvPenguinSsPleased]
+[table:sounds][value:50][name:SeSvPenguinSsAngry]
+[table:sounds][value:51][name:SeSvHoneybeeTalkNormal]
+[table:sounds][value:52][name:SeSvHoneybeeTalkConfusion]
+[table:sounds][value:53][name:SeSvHoneybeeTalkQuestion]
+[table:sounds][value:54][name:SeSvHoneybeeTalkSurprise]
+[table:sounds][value:55][name:SeSvHoneybeeTalkOrder]
+[table:sounds][value:56][name:SeSvHoneybeeTalkLaugh]
+[table:sounds][value:57][name:SeSvHoneybeeTalkGlad]
+[table:sounds][value:58][name:SeSvTicoTalkNormal]
+[table:sounds][value:59][name:SeSvTicoTalkGlad]
+[table:sounds][value:60][name:SeSvTicoTalkAngry]
+[table:sounds][value:61][name:SeSvTicoTalkSad]
+[table:sounds][value:62][name:SeSvTicoTalkHappy]
+# 63 has no name yet.
+[table:sounds][value:64][name:SeSvTicoTico]
+[table:sounds][value:65][name:SeSvTicoTalkConfusion]
+[table:sounds][value:66][name:SeSvTicoTalkThanks]
+[table:sounds][value:67][name:SeSvLuigiMario]
+[table:sounds][value:68][name:SeSvLuigiFrightened]
+[table:sounds][value:69][name:SeSvLuigiSorry]
+[table:sounds][value:70][name:SeSvLuigiThanks]
+[table:sounds][value:71][name:SeSvLuigiHey]
+[table:sounds][value:72][name:SeDmKinopioChief]
+[table:sounds][value:73][name:SeSvTereracerTalkNormal]
+[table:sounds][value:74][name:SeSvTereracerTalkLaugh]
+[table:sounds][value:75][name:SeSvTereracerTalkRegret]
+[table:sounds][value:76][name:SeSvCaretakerShort]
+[table:sounds][value:77][name:SeSvCaretakerNormal]
+[table:sounds][value:78][name:SeSvCaretakerLong]
+[table:sounds][value:79][name:SeSvCaretakerRepeat]
+# 80 to 84 have no names yet.
+[table:sounds][value:85][name:SeSvHoneyqueenTalkSurprise]
+[table:sounds][value:86][name:SeSvHoneyqueenTalkThanks]
+[table:sounds][value:87][name:SeSvHoneyqueenTalkWorry]
+[table:sounds][value:88][name:SeSvHoneyqueenTalkAa]
+[table:sounds][value:89][name:SeSvHoneyqueenTalkAn]
+[table:sounds][value:90][name:SeSvHoneyqueenTalkUfufu]
+[table:sounds][value:91][name:SeSvKinopioTalkWelcome]
+[table:sounds][value:92][name:SeSvKinopioTalkBeautiful]
+[table:sounds][value:93][name:SeSvKinopioTalkSurprise]
+[table:sounds][value:94][name:SeSvKinopioPuha]
+[table:sounds][value:95][name:SeSvKinopioTalkHelp]
+[table:sounds][value:96][name:SeSvKinopioTalkTremble]
+[table:sounds][value:97][name:SeSvKinopioTalkStrong]
+[table:sounds][value:98][name:SeSvKinopioTalkLookOut]
+[table:sounds][value:99][name:SeSvKinopioTalkWow]
+[table:sounds][value:100][name:SeSvPeachTalkHelp]
+[table:sounds][value:101][name:SeSvRosettaTalkNormal]
+[table:sounds][value:102][name:SeSvRosettaTalkSurprise]
+[table:sounds][value:103][name:SeSvRosettaTalkSmile]
+[table:sounds][value:104][name:SeSvRosettaTalkWaiting]
+[table:sounds][value:105][name:SeSvRosettaTalkWorried]
+[table:sounds][value:106][name:SeSvTicofatTalkNormal]
+[table:sounds][value:107][name:SeSvTicofatTalkKita]
+[table:sounds][value:108][name:SeSvTicofatMeta]
+[table:sounds][value:109][name:SeSvKinopiochiefTalkHey]
+[table:sounds][value:110][name:SeSvKinopiochiefTalkLaugh]
+[table:sounds][value:111][name:SeSvKinopiochiefTalkYahoo]
+[table:sounds][value:112][name:SeSvTicofatTalkGiveMe]
+[table:sounds][value:113][name:SeSvTicofatTalkWaku]
+[table:sounds][value:114][name:SeSvButlerTalkSurprise]
+[table:sounds][value:115][name:SeSvButlerTalkAgree]
+[table:sounds][value:116][name:SeSvButlerTalkWorried]
+[table:sounds][value:117][name:SeSvButlerTalkNormal]
+[table:sounds][value:118][name:SeSvPenguinOldGreet]
+[table:sounds][value:119][name:SeSvPenguinOldGrad]
+[table:sounds][value:120][name:SeSvPenguinOldSad]
+[table:sounds][value:121][name:SeSvPenguinOldNormal]
+[table:sounds][value:122][name:SeSvLuigiTalkTire]
+[table:sounds][value:123][name:SeSvLuigiTalkYah]
+[table:sounds][value:124][name:SeSvLuigiTalkHelp]
+[table:sounds][value:125][name:SeSvLuigiTalkOhYeah]
+[table:sounds][value:126][name:SeSvButlerTalkQuestion]
+[table:sounds][value:127][name:SeSvRosettaTalkLetsStart]
+[table:sounds][value:128][name:SeSvRosettaTalkSomuchToday]
+[table:sounds][value:129][name:SeSvPenguinOldScared]
+[table:sounds][value:130][name:SeSvTicocometTalkPururin]
+[table:sounds][value:131][name:SeSvTicocometTalkDon]
+[table:sounds][value:132][name:SeSvKinopioTalkWater]
+[table:sounds][value:133][name:SeSvKinopioTalkHeySnor]
+[table:sounds][value:134][name:SeSvKinopioTalkSadSnor]
+[table:sounds][value:135][name:SeSvTicoshopTalkPikarin]
+[table:sounds][value:136][name:SeSvTicoshopTalkKita]
+[table:sounds][value:137][name:SeBvKoopajrTlkProvoke]
+[table:sounds][value:138][name:SeBvKoopaTlkLaugh]
+[table:sounds][value:139][name:SeBvKoopaTlkNormal]
+[table:sounds][value:140][name:SeBvKoopaTlkRegret]
+[table:sounds][value:141][name:SeBvKoopaTlkCalm]
+[table:sounds][value:142][name:SeBvKoopaTlkExcited]
+[table:sounds][value:143][name:SeSvTicofatTalkYeah]
+[table:sounds][value:144][name:SeSvRosettaTalkThatsAll]
+[table:sounds][value:145][name:SeSvCareTakerTrample]
+[table:sounds][value:146][name:SeSvKinopioNoMail]
+[table:sounds][value:147][name:SeSvKinopioLookMail]
+[table:sounds][value:148][name:SeSvKinopioTalkShout]
+[table:sounds][value:149][name:SeSvKinopiochiefTalkEvasive]
+[table:sounds][value:150][name:SeSvRosettaTalkSo]
+[table:sounds][value:151][name:SeSvRosettaTalkLook]
+[table:sounds][value:152][name:SeSmSignboardHey]
+[table:sounds][value:153][name:SeSvRosettaTalkPlease]
+[table:sounds][value:154][name:SeSvRosettaTalkFu]
+[table:sounds][value:155][name:SeSvRosettaTalkTrouble]
+[table:sounds][value:156][name:SeSvRosettaTalkSigh]
+[table:sounds][value:157][name:SeSvRosettaTalkQuestion]
+[table:sounds][value:158][name:SeSvRosettaTalkSmileEpB]
+[table:sounds][value:159][name:SeSvHoneybeeTalkSleep]
+[table:sounds][value:160][name:SeSvKinopioTalkTired]
+[table:sounds][value:161][name:SeSvCaretakerAngryFast]
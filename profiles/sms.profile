@@ -0,0 +1,8 @@
+# Super Mario Sunshine.
+[profile:sms][entrysize:12][encoding:shift-jis]
+
+[attribute:unk0][offset:0][size:2]
+[attribute:unk1][offset:2][size:2]
+[attribute:unk2][offset:4][size:4]
+
+[escape:color][type:255][fields:u16=0,u8]
@@ -0,0 +1,30 @@
+# The Legend of Zelda: Twilight Princess.
+[profile:tp][entrysize:20][encoding:utf16]
+
+[attribute:messageid][offset:0][size:2]
+[attribute:unk][offset:2][size:2]
+[attribute:boxstyle][offset:4][size:1]
+[attribute:drawtype][offset:5][size:1][table:drawtypes]
+[attribute:boxposition][offset:6][size:1][table:boxpositions]
+[attribute:itemimage][offset:7][size:1]
+[attribute:unk2][offset:8][size:1]
+[attribute:initialsound][offset:9][size:1]
+[attribute:initialcamera][offset:10][size:1]
+[attribute:speakeranim][offset:11][size:1]
+[attribute:unk3][offset:12][size:2]
+[attribute:unk4][offset:14][size:1]
+[attribute:unk5][offset:15][size:1]
+
+[escape:player][type:0][fields:u16=0]
+[escape:choice2][type:0][fields:u16=8]
+[escape:choice3][type:0][fields:u16=9]
+[escape:item][type:0][fields:u16=29,u8]
+[escape:furigana][type:2][fields:u16,text]
+[escape:color][type:255][fields:u16=0,u8]
+
+[table:boxpositions][value:0][name:top]
+[table:boxpositions][value:1][name:center]
+[table:boxpositions][value:2][name:bottom]
+
+[table:drawtypes][value:0][name:instant]
+[table:drawtypes][value:1][name:bychar]
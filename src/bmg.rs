@@ -1,12 +1,17 @@
+use std::collections::HashMap;
 use std::io::SeekFrom;
 use std::io::*;
 use binrw::prelude::*;
 use binrw::Endian;
+use crate::encoding::Encoding;
+use crate::error::BmgError;
+use crate::profile::Profile;
 
 #[derive(Debug, Default, Clone, Copy)]
 pub struct FileHeader {
     pub magic: [u8; 8],
-    pub flw1offset: u32,
+    /// Size of the whole file, filled in by [`BMG::write`].
+    pub filesize: u32,
     pub sectioncount: u32,
     pub padding: [u8; 16]
 }
@@ -16,17 +21,41 @@ impl FileHeader {
     pub const LE_MAGIC: [u8; 8] = [b'G',b'S',b'E',b'M',b'1',b'g',b'm',b'b'];
     #[inline]
     pub fn read<R: BinReaderExt>(reader: &mut R) -> BinResult<(Self, Endian)> {
-        let magic : [u8; 8] = reader.read_ne()?;
-        let endian = match magic {
+        let pos = reader.stream_position()?;
+        let mut res = Self { magic: reader.read_ne()?, ..Default::default() };
+        if res.magic != Self::BE_MAGIC && res.magic != Self::LE_MAGIC {
+            return Err(binrw::Error::BadMagic { pos, found: Box::new(res.magic) });
+        }
+        let endian = res.endian();
+        res.filesize = reader.read_type(endian)?;
+        res.sectioncount = reader.read_type(endian)?;
+        res.padding = reader.read_ne()?;
+        Ok((res, endian))
+    }
+    /// The encoding byte, `None` if the file leaves the encoding to the game.
+    #[inline]
+    pub const fn encoding(&self) -> Option<Encoding> {
+        Encoding::from_byte(self.padding[0])
+    }
+    #[inline]
+    pub const fn set_encoding(&mut self, encoding: Encoding) {
+        self.padding[0] = encoding.byte();
+    }
+    #[inline]
+    pub const fn endian(&self) -> Endian {
+        match self.magic {
             Self::BE_MAGIC => Endian::Big,
             Self::LE_MAGIC => Endian::Little,
             _ => Endian::NATIVE
-        };
-        let flw1offset = reader.read_type(endian)?;
-        let sectioncount = reader.read_type(endian)?;
-        let padding = reader.read_ne()?;
-        let res = Self {magic, flw1offset, sectioncount, padding};
-        Ok((res, endian))
+        }
+    }
+    pub fn write<W: BinWriterExt>(&self, writer: &mut W) -> BinResult<()> {
+        let endian = self.endian();
+        writer.write_ne(&self.magic)?;
+        writer.write_type(&self.filesize, endian)?;
+        writer.write_type(&self.sectioncount, endian)?;
+        writer.write_ne(&self.padding)?;
+        Ok(())
     }
 }
 
@@ -37,23 +66,40 @@ pub enum SectionMagic {
     INF1 = 826691145,
     DAT1 = 827605316,
     FLW1 = 827804742,
-    FLI1 = 826887238
+    FLI1 = 826887238,
+    MID1 = 826558797
+}
+
+impl TryFrom<u32> for SectionMagic {
+    type Error = u32;
+    fn try_from(magic: u32) -> std::result::Result<Self, Self::Error> {
+        match magic {
+            826691145 => Ok(Self::INF1),
+            827605316 => Ok(Self::DAT1),
+            827804742 => Ok(Self::FLW1),
+            826887238 => Ok(Self::FLI1),
+            826558797 => Ok(Self::MID1),
+            _ => Err(magic)
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone, Copy)]
 pub struct SectionHeader {
-    pub magic: SectionMagic,
+    pub magic: u32,
     pub size: u32
 }
 
 impl SectionHeader {
     pub fn read<R: BinReaderExt>(reader: &mut R, endian: Endian) -> BinResult<Self> {
-        let mut result = Self::default();
-        let SectionHeader { magic, size } = &mut result;
-        let mgc = <u32>::read_le(reader)?;
-        *magic = unsafe { std::mem::transmute(mgc) };
-        *size = <u32>::read_options(reader, endian, ())?;
-        Ok(result)
+        let magic = <u32>::read_le(reader)?;
+        let size = <u32>::read_options(reader, endian, ())?;
+        Ok(Self { magic, size })
+    }
+    pub fn write<W: BinWriterExt>(&self, writer: &mut W, endian: Endian) -> BinResult<()> {
+        writer.write_le(&self.magic)?;
+        writer.write_type(&self.size, endian)?;
+        Ok(())
     }
 }
 
@@ -68,13 +114,12 @@ pub enum MessageType {
     Empty
 }
 
-#[derive(Debug, Default, Clone, Copy, BinWrite)]
-#[bw(repr = i8)]
+#[derive(Debug, Default, Clone, Copy)]
 pub enum MessageBoxType {
     #[default]
     Normal,
-    SignBoard = 4,
-    Unknown = -1
+    SignBoard,
+    Unknown(u8)
 }
 
 impl BinRead for MessageBoxType {
@@ -88,11 +133,28 @@ impl BinRead for MessageBoxType {
         match byte {
             0 => Ok(Self::Normal),
             4 => Ok(Self::SignBoard),
-            _ => Ok(Self::Unknown)
+            _ => Ok(Self::Unknown(byte))
         }
     }
 }
 
+impl BinWrite for MessageBoxType {
+    type Args<'a> = ();
+    fn write_options<W: Write + Seek>(
+            &self,
+            writer: &mut W,
+            _: Endian,
+            _: Self::Args<'_>,
+        ) -> BinResult<()> {
+        let byte: u8 = match self {
+            Self::Normal => 0,
+            Self::SignBoard => 4,
+            Self::Unknown(byte) => *byte
+        };
+        writer.write_ne(&byte)
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy, BinRead, BinWrite)]
 #[brw(repr = u8)]
 pub enum CameraType {
@@ -102,9 +164,9 @@ pub enum CameraType {
     NoCam
 }
 
+/// The INF1 attributes of Super Mario Galaxy.
 #[derive(Debug, Default, Clone, Copy, BinRead, BinWrite)]
-pub struct INF1Entry {
-    pub textaddress: u32,
+pub struct GalaxyInfo {
     pub cameraid: u16,
     pub soundid: u8,
     pub camtype: CameraType,
@@ -114,29 +176,120 @@ pub struct INF1Entry {
     pub padding: u8
 }
 
-#[derive(Debug, Clone, Default, BinRead, BinWrite)]
+/// The INF1 attributes of The Wind Waker.
+#[derive(Debug, Default, Clone, Copy, BinRead, BinWrite)]
+pub struct WindWakerInfo {
+    pub messageid: u16,
+    pub itemprice: u16,
+    pub nextmessageid: u16,
+    pub unk: u16,
+    pub boxstyle: u8,
+    pub drawtype: u8,
+    pub boxposition: u8,
+    pub itemimage: u8,
+    pub unk2: u8,
+    pub initialsound: u8,
+    pub initialcamera: u8,
+    pub speakeranim: u8,
+    pub unk3: u8,
+    pub linesperbox: u16,
+    pub unk4: u8
+}
+
+/// The INF1 attributes of Twilight Princess.
+#[derive(Debug, Default, Clone, Copy, BinRead, BinWrite)]
+pub struct TwilightInfo {
+    pub messageid: u16,
+    pub unk: u16,
+    pub boxstyle: u8,
+    pub drawtype: u8,
+    pub boxposition: u8,
+    pub itemimage: u8,
+    pub unk2: u8,
+    pub initialsound: u8,
+    pub initialcamera: u8,
+    pub speakeranim: u8,
+    pub unk3: u16,
+    pub unk4: u8,
+    pub unk5: u8
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct INF1Entry {
+    pub textaddress: u32,
+    /// The rest of the entry in file byte order. What it holds depends on the
+    /// game, see [`crate::profile::Profile`].
+    pub attributes: Vec<u8>
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct INF1 {
     pub entrynum: u16,
-    pub entrysize: u16, // 0x12
+    pub entrysize: u16,
     pub padding: u32,
-    #[br(count = entrynum as usize)]
     pub entries: Vec<INF1Entry>
 }
 
-#[derive(Debug, Clone, Default)]
+impl INF1 {
+    pub fn read<R: BinReaderExt>(reader: &mut R, endian: Endian, end: u64) -> BinResult<Self> {
+        let entrynum: u16 = reader.read_type(endian)?;
+        let entrysize: u16 = reader.read_type(endian)?;
+        let padding = reader.read_type(endian)?;
+        let pos = reader.stream_position()?;
+        if entrysize < 4 || pos + entrynum as u64 * entrysize as u64 > end {
+            let message = format!("INF1 with {entrynum} entries of {entrysize} bytes doesn't fit its section");
+            return Err(binrw::Error::AssertFail { pos, message });
+        }
+        let mut entries = Vec::with_capacity(entrynum as usize);
+        for _ in 0..entrynum {
+            let textaddress = reader.read_type(endian)?;
+            let mut attributes = vec![0; entrysize as usize - 4];
+            reader.read_exact(&mut attributes)?;
+            entries.push(INF1Entry { textaddress, attributes });
+        }
+        Ok(Self { entrynum, entrysize, padding, entries })
+    }
+    pub fn write<W: BinWriterExt>(&self, writer: &mut W, endian: Endian) -> BinResult<()> {
+        writer.write_type(&(self.entries.len() as u16), endian)?;
+        writer.write_type(&self.entrysize, endian)?;
+        writer.write_type(&self.padding, endian)?;
+        for entry in &self.entries {
+            writer.write_type(&entry.textaddress, endian)?;
+            writer.write_all(&entry.attributes)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct DAT1 {
-    pub padding: u8,
+    pub endian: Endian,
+    pub encoding: Encoding,
     pub data: Vec<u8>,
 }
 
+impl Default for DAT1 {
+    fn default() -> Self {
+        Self { endian: Endian::Big, encoding: Encoding::Utf16, data: vec![] }
+    }
+}
+
 impl DAT1 {
-    pub fn read<R: BinReaderExt>(reader: &mut R, end: u64) -> BinResult<Self> {
-        let mut result = Self::default();
-        result.padding = reader.read_ne()?;
+    pub fn read<R: BinReaderExt>(reader: &mut R, endian: Endian, end: u64) -> BinResult<Self> {
         let size = end - reader.stream_position()?;
-        result.data.resize(size as usize, 0);
-        reader.read_exact(&mut result.data)?;
-        Ok(result)
+        let mut data = vec![0; size as usize];
+        reader.read_exact(&mut data)?;
+        Ok(Self { endian, data, ..Default::default() })
+    }
+    pub fn write<W: BinWriterExt>(&self, writer: &mut W) -> BinResult<()> {
+        writer.write_all(&self.data)?;
+        Ok(())
+    }
+    pub fn push_string(&mut self, bytes: &[u8]) -> u32 {
+        let address = self.data.len() as u32;
+        self.data.extend_from_slice(bytes);
+        self.data.resize(self.data.len() + self.encoding.unit(), 0);
+        address
     }
 }
 
@@ -226,13 +379,18 @@ pub struct FLW1 {
 }
 
 impl FLW1 {
-    pub fn read<R: BinReaderExt>(reader: &mut R, endian: Endian) -> BinResult<Self> {
+    pub fn read<R: BinReaderExt>(reader: &mut R, endian: Endian, end: u64) -> BinResult<Self> {
         let mut result = Self::default();
         let FLW1 { nodenum, branchnodenum, padding,
             entries, branch_nodes } = &mut result;
         *nodenum = reader.read_type(endian)?;
         *branchnodenum = reader.read_type(endian)?;
         *padding = reader.read_type(endian)?;
+        let pos = reader.stream_position()?;
+        if pos + *nodenum as u64 * 8 + *branchnodenum as u64 * 2 > end {
+            let message = format!("FLW1 with {nodenum} nodes and {branchnodenum} branches doesn't fit its section");
+            return Err(binrw::Error::AssertFail { pos, message });
+        }
         entries.reserve_exact(*nodenum as usize);
         for _ in 0..*nodenum {
             entries.push(FLW1Entry::read(reader, endian)?);
@@ -242,13 +400,124 @@ impl FLW1 {
         }
         Ok(result)
     }
+    pub fn write<W: BinWriterExt>(&self, writer: &mut W, endian: Endian) -> BinResult<()> {
+        writer.write_type(&(self.entries.len() as u16), endian)?;
+        writer.write_type(&(self.branch_nodes.len() as u16), endian)?;
+        writer.write_type(&self.padding, endian)?;
+        for entry in &self.entries {
+            entry.write(writer, endian)?;
+        }
+        writer.write_type(&self.branch_nodes, endian)?;
+        Ok(())
+    }
 }
 
-#[derive(Debug, Default, Clone, Copy, BinRead, BinWrite)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinRead, BinWrite)]
+pub struct FLI1Entry {
+    pub id: u32,
+    pub node: u16,
+    pub padding: u16
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct FLI1 {
     pub entrynum: u16,
     pub entrylength: u8,
-    pub padding: [u8; 5]
+    pub padding: [u8; 5],
+    pub entries: Vec<FLI1Entry>,
+    /// Anything between the entries and the end of the section that isn't
+    /// plain alignment.
+    pub trailing: Vec<u8>
+}
+
+impl FLI1 {
+    pub fn read<R: BinReaderExt>(reader: &mut R, endian: Endian, end: u64) -> BinResult<Self> {
+        let entrynum: u16 = reader.read_type(endian)?;
+        let entrylength = reader.read_ne()?;
+        let padding = reader.read_ne()?;
+        let pos = reader.stream_position()?;
+        if entrylength != 8 || pos + entrynum as u64 * 8 > end {
+            let message = format!("FLI1 with {entrynum} entries of {entrylength} bytes doesn't fit its section");
+            return Err(binrw::Error::AssertFail { pos, message });
+        }
+        let mut entries = Vec::with_capacity(entrynum as usize);
+        for _ in 0..entrynum {
+            entries.push(reader.read_type(endian)?);
+        }
+        let pos = reader.stream_position()?;
+        let mut trailing = vec![0; (end - pos) as usize];
+        reader.read_exact(&mut trailing)?;
+        if align(pos) == end && trailing.iter().all(|&b| b == 0) {
+            trailing.clear();
+        }
+        Ok(Self { entrynum, entrylength, padding, entries, trailing })
+    }
+    pub fn write<W: BinWriterExt>(&self, writer: &mut W, endian: Endian) -> BinResult<()> {
+        writer.write_type(&(self.entries.len() as u16), endian)?;
+        writer.write_ne(&self.entrylength)?;
+        writer.write_ne(&self.padding)?;
+        writer.write_type(&self.entries, endian)?;
+        writer.write_all(&self.trailing)?;
+        Ok(())
+    }
+    /// Keeps the entries in step with [`FLW1::remove_node`]: entries that
+    /// started at the node are dropped and later nodes move down by one.
+    pub fn remove_node(&mut self, id: u16) {
+        self.entries.retain(|entry| entry.node != id);
+        for entry in &mut self.entries {
+            if entry.node > id {
+                entry.node -= 1;
+            }
+        }
+        self.entrynum = self.entries.len() as u16;
+    }
+}
+
+/// Message ids, one for each INF1 entry.
+#[derive(Debug, Default, Clone)]
+pub struct MID1 {
+    pub entrynum: u16,
+    pub format: u8,
+    pub info: u8,
+    pub padding: u32,
+    pub ids: Vec<u32>,
+    /// Anything between the ids and the end of the section that isn't plain
+    /// alignment.
+    pub trailing: Vec<u8>
+}
+
+impl MID1 {
+    pub fn read<R: BinReaderExt>(reader: &mut R, endian: Endian, end: u64) -> BinResult<Self> {
+        let entrynum: u16 = reader.read_type(endian)?;
+        let format = reader.read_ne()?;
+        let info = reader.read_ne()?;
+        let padding = reader.read_type(endian)?;
+        let pos = reader.stream_position()?;
+        if pos + entrynum as u64 * 4 > end {
+            let message = format!("MID1 with {entrynum} ids doesn't fit its section");
+            return Err(binrw::Error::AssertFail { pos, message });
+        }
+        let mut ids = Vec::with_capacity(entrynum as usize);
+        for _ in 0..entrynum {
+            ids.push(reader.read_type(endian)?);
+        }
+        let pos = reader.stream_position()?;
+        let mut trailing = vec![0; (end - pos) as usize];
+        reader.read_exact(&mut trailing)?;
+        if align(pos) == end && trailing.iter().all(|&b| b == 0) {
+            trailing.clear();
+        }
+        Ok(Self { entrynum, format, info, padding, ids, trailing })
+    }
+    pub fn write<W: BinWriterExt>(&self, writer: &mut W, endian: Endian) -> BinResult<()> {
+        writer.write_type(&(self.ids.len() as u16), endian)?;
+        writer.write_ne(&self.format)?;
+        writer.write_ne(&self.info)?;
+        writer.write_type(&self.padding, endian)?;
+        writer.write_type(&self.ids, endian)?;
+        writer.write_all(&self.trailing)?;
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -256,7 +525,12 @@ pub enum Section {
     INF1(INF1),
     DAT1(DAT1),
     FLW1(FLW1),
-    FLI1(FLI1)
+    FLI1(FLI1),
+    MID1(MID1),
+    Unknown {
+        magic: u32,
+        data: Vec<u8>
+    }
 }
 
 impl Default for Section {
@@ -266,41 +540,239 @@ impl Default for Section {
 }
 
 impl Section {
-    pub fn read<R: BinReaderExt>(reader: &mut R, endian: Endian) -> BinResult<Self> {
+    /// Reads the section at the reader's position. Errors carry the offset of
+    /// the section.
+    pub fn read<R: BinReaderExt>(reader: &mut R, endian: Endian) -> std::result::Result<Self, BmgError> {
         let pos = reader.stream_position()?;
-        let section_header = SectionHeader::read(reader, endian)?;
-        let mut end = pos + section_header.size as u64;
-        end = end + ((end + 31 & !31) - end);
-        let res = match section_header.magic {
-            SectionMagic::INF1 => Self::INF1(reader.read_type(endian)?),
-            SectionMagic::DAT1 => Self::DAT1(DAT1::read(reader, end)?),
-            SectionMagic::FLW1 => Self::FLW1(FLW1::read(reader, endian)?),
-            SectionMagic::FLI1 => Self::FLI1(reader.read_type(endian)?)
+        let bad_section = |reason: String| BmgError::BadSection { offset: pos, reason };
+        let len = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(pos))?;
+        let section_header = SectionHeader::read(reader, endian).map_err(|e| bad_section(e.to_string()))?;
+        let magic = String::from_utf8_lossy(&section_header.magic.to_le_bytes()).into_owned();
+        let size = section_header.size as u64;
+        if size < 8 {
+            return Err(bad_section(format!("{magic} is {size} bytes, less than its header")));
+        }
+        if pos + size > len {
+            return Err(bad_section(format!("{magic} is {size} bytes and runs past the end of the file")));
+        }
+        // The last section may leave out its alignment.
+        let end = align(pos + size).min(len);
+        Self::read_body(reader, endian, section_header.magic, end)
+            .map_err(|e| bad_section(format!("{magic}: {e}")))
+    }
+    fn read_body<R: BinReaderExt>(reader: &mut R, endian: Endian, magic: u32, end: u64) -> BinResult<Self> {
+        let res = match SectionMagic::try_from(magic) {
+            Ok(SectionMagic::INF1) => Self::INF1(INF1::read(reader, endian, end)?),
+            Ok(SectionMagic::DAT1) => Self::DAT1(DAT1::read(reader, endian, end)?),
+            Ok(SectionMagic::FLW1) => Self::FLW1(FLW1::read(reader, endian, end)?),
+            // Not every game fills FLI1 and MID1 the same way, keep the ones that don't parse as they are.
+            Ok(SectionMagic::FLI1) => {
+                let start = reader.stream_position()?;
+                match FLI1::read(reader, endian, end) {
+                    Ok(fli1) => Self::FLI1(fli1),
+                    Err(_) => {
+                        reader.seek(SeekFrom::Start(start))?;
+                        Self::read_unknown(reader, magic, end)?
+                    }
+                }
+            },
+            Ok(SectionMagic::MID1) => {
+                let start = reader.stream_position()?;
+                match MID1::read(reader, endian, end) {
+                    Ok(mid1) => Self::MID1(mid1),
+                    Err(_) => {
+                        reader.seek(SeekFrom::Start(start))?;
+                        Self::read_unknown(reader, magic, end)?
+                    }
+                }
+            },
+            Err(magic) => Self::read_unknown(reader, magic, end)?
         };
         reader.seek(SeekFrom::Start(end))?;
         Ok(res)
     }
+    fn read_unknown<R: BinReaderExt>(reader: &mut R, magic: u32, end: u64) -> BinResult<Self> {
+        let size = end - reader.stream_position()?;
+        let mut data = vec![0; size as usize];
+        reader.read_exact(&mut data)?;
+        Ok(Self::Unknown { magic, data })
+    }
+    #[inline]
+    pub const fn magic(&self) -> u32 {
+        match self {
+            Self::INF1(_) => SectionMagic::INF1 as u32,
+            Self::DAT1(_) => SectionMagic::DAT1 as u32,
+            Self::FLW1(_) => SectionMagic::FLW1 as u32,
+            Self::FLI1(_) => SectionMagic::FLI1 as u32,
+            Self::MID1(_) => SectionMagic::MID1 as u32,
+            Self::Unknown { magic, .. } => *magic
+        }
+    }
+    #[inline]
+    pub fn name(&self) -> String {
+        String::from_utf8_lossy(&self.magic().to_le_bytes()).into_owned()
+    }
+    pub fn write<W: BinWriterExt>(&self, writer: &mut W, endian: Endian) -> BinResult<()> {
+        let pos = writer.stream_position()?;
+        let mut section_header = SectionHeader { magic: self.magic(), size: 0 };
+        section_header.write(writer, endian)?;
+        match self {
+            Self::INF1(inf1) => inf1.write(writer, endian)?,
+            Self::DAT1(dat1) => dat1.write(writer)?,
+            Self::FLW1(flw1) => flw1.write(writer, endian)?,
+            Self::FLI1(fli1) => fli1.write(writer, endian)?,
+            Self::MID1(mid1) => mid1.write(writer, endian)?,
+            Self::Unknown { data, .. } => writer.write_all(data)?
+        }
+        let end = align(writer.stream_position()?);
+        let padding = end - writer.stream_position()?;
+        writer.write_all(&vec![0; padding as usize])?;
+        section_header.size = (end - pos) as u32;
+        writer.seek(SeekFrom::Start(pos))?;
+        section_header.write(writer, endian)?;
+        writer.seek(SeekFrom::Start(end))?;
+        Ok(())
+    }
+}
+
+#[inline]
+const fn align(pos: u64) -> u64 {
+    (pos + 31) & !31
 }
 
 #[derive(Debug, Default, Clone)]
 pub struct BMG {
     pub header: FileHeader,
-    pub sections: Vec<Section>
+    pub sections: Vec<Section>,
+    /// The game the file belongs to, picked by [`BMG::read`] from the sections.
+    pub profile: Profile
 }
 
 impl BMG {
-    pub fn read<R: BinReaderExt>(reader: &mut R) -> BinResult<Self> {
+    #[inline]
+    pub fn read<R: BinReaderExt>(reader: &mut R) -> std::result::Result<Self, BmgError> {
+        Self::read_with(reader, None)
+    }
+    /// Reads a BMG as a file of the given game, or detects the game if there's none.
+    pub fn read_with<R: BinReaderExt>(reader: &mut R, profile: Option<Profile>) -> std::result::Result<Self, BmgError> {
         let mut result = Self::default();
-        let BMG {header, sections} = &mut result;
+        let BMG {header, sections, ..} = &mut result;
         let endian;
         (*header, endian) = FileHeader::read(reader)?;
-        sections.reserve_exact(header.sectioncount as usize);
         for _ in 0..header.sectioncount {
             sections.push(Section::read(reader, endian)?);
         }
+        result.profile = profile.unwrap_or_else(|| Profile::detect(&result.header, &result.sections));
+        let encoding = result.encoding();
+        if let Some(dat1) = result.get_dat1_mut() {
+            dat1.encoding = encoding;
+        }
         Ok(result)
     }
     #[inline]
+    pub fn from_bytes(data: &[u8]) -> std::result::Result<Self, BmgError> {
+        Self::read(&mut Cursor::new(data))
+    }
+    #[inline]
+    pub fn from_bytes_with(data: &[u8], profile: Option<Profile>) -> std::result::Result<Self, BmgError> {
+        Self::read_with(&mut Cursor::new(data), profile)
+    }
+    #[inline]
+    pub fn to_bytes(&self) -> BinResult<Vec<u8>> {
+        let mut data = Cursor::new(vec![]);
+        self.write(&mut data)?;
+        Ok(data.into_inner())
+    }
+    pub fn write<W: BinWriterExt>(&self, writer: &mut W) -> BinResult<()> {
+        let start = writer.stream_position()?;
+        let endian = self.header.endian();
+        let mut header = self.header;
+        header.sectioncount = self.sections.len() as u32;
+        header.write(writer)?;
+        for section in &self.sections {
+            section.write(writer, endian)?;
+        }
+        let end = writer.stream_position()?;
+        header.filesize = (end - start) as u32;
+        writer.seek(SeekFrom::Start(start))?;
+        header.write(writer)?;
+        writer.seek(SeekFrom::Start(end))?;
+        Ok(())
+    }
+    pub fn set_endian(&mut self, endian: Endian) -> std::result::Result<(), BmgError> {
+        if endian == self.header.endian() {
+            return Ok(());
+        }
+        let addresses = self.get_inf1()
+            .map(|inf1| inf1.entries.iter().map(|e| e.textaddress).collect::<Vec<_>>())
+            .unwrap_or_default();
+        // DAT1 is the only part that can fail, the rest follows once it's done.
+        for section in &mut self.sections {
+            if let Section::DAT1(dat1) = section {
+                dat1.set_endian(endian, &addresses, &self.profile)?;
+            }
+        }
+        for section in &mut self.sections {
+            if let Section::INF1(inf1) = section {
+                inf1.entries.iter_mut().for_each(|entry| self.profile.swap_attributes(&mut entry.attributes));
+            }
+        }
+        self.header.magic = match endian {
+            Endian::Big => FileHeader::BE_MAGIC,
+            Endian::Little => FileHeader::LE_MAGIC
+        };
+        Ok(())
+    }
+    /// The encoding of the text, from the header or else from the profile.
+    #[inline]
+    pub fn encoding(&self) -> Encoding {
+        self.header.encoding().unwrap_or(self.profile.encoding)
+    }
+    /// Writes all text in another encoding. DAT1 is rebuilt in message order,
+    /// and nothing changes when a message can't be written in `encoding`.
+    pub fn set_encoding(&mut self, encoding: Encoding) -> std::result::Result<(), BmgError> {
+        let endian = self.header.endian();
+        let (Some(inf1), Some(dat1)) = (self.get_inf1(), self.get_dat1()) else {
+            if let Some(dat1) = self.get_dat1_mut() {
+                dat1.encoding = encoding;
+            }
+            self.header.set_encoding(encoding);
+            return Ok(());
+        };
+        if dat1.encoding == encoding {
+            self.header.set_encoding(encoding);
+            return Ok(());
+        }
+        let mut target = DAT1 { endian, encoding, data: vec![] };
+        let mut moved = HashMap::new();
+        let mut addresses = Vec::with_capacity(inf1.entries.len());
+        for (i, entry) in inf1.entries.iter().enumerate() {
+            let address = match moved.get(&entry.textaddress) {
+                Some(&address) => address,
+                None => {
+                    let segments = self.profile.get_segments(inf1, i, dat1)?;
+                    let bytes = self.profile.encode(&segments, endian, encoding)
+                        .map_err(|error| BmgError::Unencodable { message: i, reason: error.reason })?;
+                    let address = target.push_string(&bytes);
+                    moved.insert(entry.textaddress, address);
+                    address
+                }
+            };
+            addresses.push(address);
+        }
+        if let Some(inf1) = self.get_inf1_mut() {
+            for (entry, address) in inf1.entries.iter_mut().zip(addresses) {
+                entry.textaddress = address;
+            }
+        }
+        if let Some(dat1) = self.get_dat1_mut() {
+            *dat1 = target;
+        }
+        self.header.set_encoding(encoding);
+        Ok(())
+    }
+    #[inline]
     pub fn get_inf1(&self) -> Option<&INF1> {
         for section in &self.sections {
             if let Section::INF1(inf1) = section {
@@ -328,6 +800,24 @@ impl BMG {
         None
     }
     #[inline]
+    pub fn get_fli1(&self) -> Option<&FLI1> {
+        for section in &self.sections {
+            if let Section::FLI1(fli1) = section {
+                return Some(fli1);
+            }
+        }
+        None
+    }
+    #[inline]
+    pub fn get_mid1(&self) -> Option<&MID1> {
+        for section in &self.sections {
+            if let Section::MID1(mid1) = section {
+                return Some(mid1);
+            }
+        }
+        None
+    }
+    #[inline]
     pub fn get_inf1_mut(&mut self) -> Option<&mut INF1> {
         for section in &mut self.sections {
             if let Section::INF1(inf1) = section {
@@ -354,5 +844,193 @@ impl BMG {
         }
         None
     }
+    #[inline]
+    pub fn get_fli1_mut(&mut self) -> Option<&mut FLI1> {
+        for section in &mut self.sections {
+            if let Section::FLI1(fli1) = section {
+                return Some(fli1);
+            }
+        }
+        None
+    }
+    #[inline]
+    pub fn get_mid1_mut(&mut self) -> Option<&mut MID1> {
+        for section in &mut self.sections {
+            if let Section::MID1(mid1) = section {
+                return Some(mid1);
+            }
+        }
+        None
+    }
+    /// The INF1 index of the message with a MID1 id.
+    pub fn find_message(&self, id: u32) -> Option<usize> {
+        self.get_mid1()?.ids.iter().position(|&i| i == id)
+    }
+    /// Removes a flow node and keeps FLI1 pointing at the right nodes.
+    pub fn remove_flow_node(&mut self, id: u16) -> std::result::Result<(), BmgError> {
+        self.get_flw1_mut().ok_or(BmgError::MissingSection(SectionMagic::FLW1))?.remove_node(id)?;
+        if let Some(fli1) = self.get_fli1_mut() {
+            fli1.remove_node(id);
+        }
+        Ok(())
+    }
+
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::encoding::Encoding;
+    use std::io::Cursor;
+
+    /// Two messages and a flow whose choice leads to an event or to the
+    /// second message.
+    pub(crate) const SAMPLE: [u8; 224] = [
+        0x4d, 0x45, 0x53, 0x47, 0x62, 0x6d, 0x67, 0x31, 0x00, 0x00, 0x00, 0xe0, 0x00, 0x00, 0x00, 0x04,
+        0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x49, 0x4e, 0x46, 0x31, 0x00, 0x00, 0x00, 0x40, 0x00, 0x02, 0x00, 0x0c, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x05, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08,
+        0x00, 0x01, 0x00, 0x02, 0x00, 0x04, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x44, 0x41, 0x54, 0x31, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x00, 0x48, 0x00, 0x69, 0x00, 0x00,
+        0x00, 0x42, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x46, 0x4c, 0x57, 0x31, 0x00, 0x00, 0x00, 0x40, 0x00, 0x04, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00,
+        0x01, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x01, 0x00, 0x00, 0x01, 0xff, 0xff, 0x00, 0x00, 0x03, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x03,
+        0x00, 0x03, 0x00, 0x02, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x46, 0x4c, 0x49, 0x31, 0x00, 0x00, 0x00, 0x20, 0x00, 0x01, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    fn write(bmg: &BMG) -> Vec<u8> {
+        let mut writer = Cursor::new(Vec::new());
+        bmg.write(&mut writer).unwrap();
+        writer.into_inner()
+    }
 
+    fn check_layout(bytes: &[u8], endian: Endian) -> Vec<usize> {
+        let read = |at: usize| {
+            let word = bytes[at..(at + 4)].try_into().unwrap();
+            match endian {
+                Endian::Big => u32::from_be_bytes(word),
+                Endian::Little => u32::from_le_bytes(word)
+            }
+        };
+        let mut offsets = vec![];
+        let mut offset = 32;
+        for _ in 0..read(12) {
+            let size = read(offset + 4) as usize;
+            assert_eq!(size % 32, 0, "section at {offset} isn't aligned");
+            offsets.push(offset);
+            offset += size;
+        }
+        assert_eq!(offset, bytes.len());
+        offsets
+    }
+
+    #[test]
+    fn write_round_trip() {
+        let bmg = BMG::read(&mut Cursor::new(&SAMPLE[..])).unwrap();
+        assert_eq!(write(&bmg), SAMPLE);
+    }
+
+    #[test]
+    fn set_endian_round_trip() {
+        let mut bmg = BMG::read(&mut Cursor::new(&SAMPLE[..])).unwrap();
+        bmg.set_endian(Endian::Little).unwrap();
+        let little = write(&bmg);
+        check_layout(&little, Endian::Little);
+        let mut read = BMG::read(&mut Cursor::new(&little)).unwrap();
+        let (inf1, dat1) = (read.get_inf1().unwrap(), read.get_dat1().unwrap());
+        let profile = &read.profile;
+        assert_eq!((profile.get_string(inf1, 0, dat1).unwrap().as_str(), profile.get_string(inf1, 1, dat1).unwrap().as_str()), ("Hi", "B"));
+        assert_eq!(write(&read), little);
+        read.set_endian(Endian::Big).unwrap();
+        assert_eq!(write(&read), SAMPLE);
+        // A message past the end of DAT1 leaves the whole file as it was.
+        read.get_inf1_mut().unwrap().entries[1].textaddress = 0x100;
+        let before = write(&read);
+        assert!(matches!(read.set_endian(Endian::Little), Err(BmgError::OutOfBounds { .. })));
+        assert_eq!(write(&read), before);
+    }
+
+    #[test]
+    fn set_endian_swaps_shared_text_once() {
+        let mut dat1 = DAT1 { endian: Endian::Big, encoding: Encoding::Utf16, data: vec![0, 0x48, 0, 0x69, 0, 0, 0, 0] };
+        dat1.set_endian(Endian::Little, &[0, 2, 0], &Profile::default()).unwrap();
+        assert_eq!(dat1.data, [0x48, 0, 0x69, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn fli1_entries() {
+        let mut bmg = BMG::read(&mut Cursor::new(&SAMPLE[..])).unwrap();
+        let fli1 = bmg.get_fli1().unwrap();
+        assert_eq!(fli1.entries, [FLI1Entry { id: 7, node: 0, padding: 0 }]);
+        assert!(fli1.trailing.is_empty());
+        bmg.get_fli1_mut().unwrap().entries.push(FLI1Entry { id: 8, node: 3, padding: 0 });
+        bmg.remove_flow_node(1).unwrap();
+        let fli1 = bmg.get_fli1().unwrap();
+        assert_eq!(fli1.entries.iter().map(|entry| (entry.id, entry.node)).collect::<Vec<_>>(), [(7, 0), (8, 2)]);
+        let read = BMG::read(&mut Cursor::new(write(&bmg))).unwrap();
+        assert_eq!(read.get_fli1().unwrap().entries, bmg.get_fli1().unwrap().entries);
+        bmg.remove_flow_node(0).unwrap();
+        assert_eq!(bmg.get_fli1().unwrap().entries, [FLI1Entry { id: 8, node: 1, padding: 0 }]);
+        assert_eq!(bmg.get_fli1().unwrap().entrynum, 1);
+        // Entries of another length are kept as they are.
+        let mut bytes = SAMPLE;
+        bytes[0xca] = 12;
+        let bmg = BMG::from_bytes(&bytes).unwrap();
+        assert!(bmg.get_fli1().is_none());
+        assert!(matches!(bmg.sections[3], Section::Unknown { magic, .. } if magic == SectionMagic::FLI1 as u32));
+        assert_eq!(write(&bmg), bytes);
+    }
+
+    #[test]
+    fn set_encoding_rebuilds_dat1() {
+        let mut bmg = BMG::read(&mut Cursor::new(&SAMPLE[..])).unwrap();
+        bmg.set_encoding(Encoding::Utf8).unwrap();
+        let read = BMG::read(&mut Cursor::new(write(&bmg))).unwrap();
+        assert_eq!((read.header.padding[0], read.encoding()), (4, Encoding::Utf8));
+        let (inf1, dat1) = (read.get_inf1().unwrap(), read.get_dat1().unwrap());
+        assert_eq!(dat1.data[..5], [b'H', b'i', 0, b'B', 0]);
+        assert_eq!(read.profile.get_string(inf1, 1, dat1).unwrap(), "B");
+        bmg.get_dat1_mut().unwrap().data[..2].copy_from_slice("é".as_bytes());
+        let before = write(&bmg);
+        let error = bmg.set_encoding(Encoding::ShiftJis).unwrap_err();
+        assert!(matches!(error, BmgError::Unencodable { message: 0, .. }), "{error}");
+        assert_eq!(write(&bmg), before);
+    }
+
+    #[test]
+    fn bad_sections_have_offsets() {
+        let offset = |bytes: &[u8]| match BMG::from_bytes(bytes) {
+            Err(BmgError::BadSection { offset, .. }) => offset,
+            other => panic!("{other:?}")
+        };
+        let mut bytes = SAMPLE;
+        bytes[0x27] = 4;
+        assert_eq!(offset(&bytes), 0x20);
+        bytes[0x24] = 0xff;
+        assert_eq!(offset(&bytes), 0x20);
+        let mut bytes = SAMPLE;
+        bytes[0x88] = 0xff;
+        assert_eq!(offset(&bytes), 0x80);
+        assert_eq!(offset(&SAMPLE[..0xd0]), 0xc0);
+        // The last section can leave out its alignment.
+        let mut bytes = SAMPLE[..0xd8].to_vec();
+        bytes[0xc7] = 0x18;
+        let bmg = BMG::from_bytes(&bytes).unwrap();
+        assert_eq!(bmg.get_fli1().unwrap().entries, [FLI1Entry { id: 7, node: 0, padding: 0 }]);
+    }
+
+    #[test]
+    fn write_recomputes_sizes() {
+        let mut bmg = BMG::read(&mut Cursor::new(&SAMPLE[..])).unwrap();
+        bmg.get_dat1_mut().unwrap().data.extend([0; 40]);
+        bmg.sections.pop();
+        let bytes = write(&bmg);
+        let offsets = check_layout(&bytes, Endian::Big);
+        assert_eq!(offsets, [32, 96, 192]);
+        assert_eq!(bytes[8..12], (bytes.len() as u32).to_be_bytes());
+    }
 }
\ No newline at end of file
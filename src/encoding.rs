@@ -0,0 +1,111 @@
+//! Text encodings of DAT1 strings.
+//!
+//! The first byte of the padding in the file header says which one a file
+//! uses, 0 leaves it to the game.
+//!
+//! UTF-16 text is made of 2 byte units in the file's byte order and so is the
+//! 0x1A that starts an escape. The other encodings are byte based: an escape
+//! starts with a single 0x1A byte, which no multi-byte character contains.
+
+use binrw::Endian;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Cp1252,
+    #[default]
+    Utf16,
+    ShiftJis,
+    Utf8
+}
+
+impl Encoding {
+    /// The value of the encoding byte in the file header.
+    #[inline]
+    pub const fn byte(self) -> u8 {
+        match self {
+            Self::Cp1252 => 1,
+            Self::Utf16 => 2,
+            Self::ShiftJis => 3,
+            Self::Utf8 => 4
+        }
+    }
+    #[inline]
+    pub const fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            1 => Some(Self::Cp1252),
+            2 => Some(Self::Utf16),
+            3 => Some(Self::ShiftJis),
+            4 => Some(Self::Utf8),
+            _ => None
+        }
+    }
+    /// The name profile files and the command line use.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Cp1252 => "cp1252",
+            Self::Utf16 => "utf16",
+            Self::ShiftJis => "shift-jis",
+            Self::Utf8 => "utf8"
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().replace(['-', '_'], "").as_str() {
+            "cp1252" | "windows1252" => Some(Self::Cp1252),
+            "utf16" => Some(Self::Utf16),
+            "shiftjis" | "sjis" => Some(Self::ShiftJis),
+            "utf8" => Some(Self::Utf8),
+            _ => None
+        }
+    }
+    /// Size of a code unit, of the escape character and of the terminator.
+    #[inline]
+    pub const fn unit(self) -> usize {
+        match self {
+            Self::Utf16 => 2,
+            _ => 1
+        }
+    }
+    /// Reads the code unit at the start of `bytes`.
+    #[inline]
+    pub fn read_unit(self, bytes: &[u8], endian: Endian) -> Option<u16> {
+        match (self, endian) {
+            (Self::Utf16, Endian::Big) => Some(u16::from_be_bytes(bytes.get(..2)?.try_into().ok()?)),
+            (Self::Utf16, Endian::Little) => Some(u16::from_le_bytes(bytes.get(..2)?.try_into().ok()?)),
+            _ => bytes.first().map(|&byte| byte as u16)
+        }
+    }
+    pub fn push_unit(self, result: &mut Vec<u8>, unit: u16, endian: Endian) {
+        match (self, endian) {
+            (Self::Utf16, Endian::Big) => result.extend_from_slice(&unit.to_be_bytes()),
+            (Self::Utf16, Endian::Little) => result.extend_from_slice(&unit.to_le_bytes()),
+            _ => result.push(unit as u8)
+        }
+    }
+    pub fn decode(self, bytes: &[u8], endian: Endian) -> String {
+        match self {
+            Self::Utf16 => {
+                let units = bytes.chunks(2).filter_map(|unit| self.read_unit(unit, endian)).collect::<Vec<_>>();
+                String::from_utf16_lossy(&units)
+            },
+            Self::Cp1252 => encoding_rs::WINDOWS_1252.decode_without_bom_handling(bytes).0.into_owned(),
+            Self::ShiftJis => encoding_rs::SHIFT_JIS.decode_without_bom_handling(bytes).0.into_owned(),
+            Self::Utf8 => String::from_utf8_lossy(bytes).into_owned()
+        }
+    }
+    /// `None` if the text has characters the encoding doesn't have.
+    pub fn encode(self, text: &str, endian: Endian) -> Option<Vec<u8>> {
+        match self {
+            Self::Utf16 => {
+                let mut result = Vec::with_capacity(text.len() * 2);
+                text.encode_utf16().for_each(|unit| self.push_unit(&mut result, unit, endian));
+                Some(result)
+            },
+            Self::Cp1252 | Self::ShiftJis => {
+                let codec = if self == Self::Cp1252 { encoding_rs::WINDOWS_1252 } else { encoding_rs::SHIFT_JIS };
+                let (bytes, _, unmappable) = codec.encode(text);
+                (!unmappable).then(|| bytes.into_owned())
+            },
+            Self::Utf8 => Some(text.as_bytes().to_vec())
+        }
+    }
+}
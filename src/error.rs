@@ -0,0 +1,134 @@
+use crate::bmg::SectionMagic;
+use crate::flow::Edge;
+use crate::markup::MarkupError;
+use crate::project::ProjectError;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum BmgError {
+    Io(std::io::Error),
+    Binary(binrw::Error),
+    Format(fmt::Error),
+    Markup(MarkupError),
+    Project(ProjectError),
+    MissingSection(SectionMagic),
+    BadSection {
+        offset: u64,
+        reason: String
+    },
+    OutOfBounds {
+        section: SectionMagic,
+        offset: usize,
+        message: Option<usize>
+    },
+    BadEscape {
+        offset: usize,
+        message: Option<usize>,
+        reason: String
+    },
+    DanglingFlowRef {
+        node: u16,
+        target: u16
+    },
+    BadBranch {
+        node: u16,
+        branch: u16
+    },
+    NoSuchNode(u16),
+    Unencodable {
+        message: usize,
+        reason: String
+    },
+    NoSuchEdge {
+        node: u16,
+        edge: Edge
+    }
+}
+
+impl BmgError {
+    pub fn in_message(mut self, index: usize) -> Self {
+        if let Self::OutOfBounds { message, .. } | Self::BadEscape { message, .. } = &mut self {
+            message.get_or_insert(index);
+        }
+        self
+    }
+}
+
+impl fmt::Display for BmgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let in_message = |f: &mut fmt::Formatter<'_>, message: &Option<usize>| match message {
+            Some(message) => write!(f, " in message {message}"),
+            None => Ok(())
+        };
+        match self {
+            Self::Io(error) => write!(f, "{error}"),
+            Self::Binary(error) => write!(f, "{error}"),
+            Self::Format(error) => write!(f, "{error}"),
+            Self::Markup(error) => write!(f, "{error}"),
+            Self::Project(error) => write!(f, "{error}"),
+            Self::MissingSection(section) => write!(f, "the file has no {section:?} section"),
+            Self::BadSection { offset, reason } => write!(f, "bad section at offset {offset:#x}: {reason}"),
+            Self::OutOfBounds { section, offset, message } => {
+                write!(f, "{section:?} offset {offset:#x} is out of bounds")?;
+                in_message(f, message)
+            },
+            Self::BadEscape { offset, message, reason } => {
+                write!(f, "bad escape at DAT1 offset {offset:#x}")?;
+                in_message(f, message)?;
+                write!(f, ": {reason}")
+            },
+            Self::DanglingFlowRef { node, target } => {
+                write!(f, "flow node {node} points at node {target}, which doesn't exist")
+            },
+            Self::BadBranch { node, branch } => {
+                write!(f, "flow node {node} uses branch {branch}, which doesn't exist")
+            },
+            Self::NoSuchNode(node) => write!(f, "there is no flow node {node}"),
+            Self::Unencodable { message, reason } => write!(f, "message {message} can't be re-encoded: {reason}"),
+            Self::NoSuchEdge { node, edge } => write!(f, "flow node {node} has no {edge:?} edge")
+        }
+    }
+}
+
+impl std::error::Error for BmgError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Binary(error) => Some(error),
+            Self::Format(error) => Some(error),
+            Self::Markup(error) => Some(error),
+            Self::Project(error) => Some(error),
+            _ => None
+        }
+    }
+}
+
+impl From<std::io::Error> for BmgError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<binrw::Error> for BmgError {
+    fn from(error: binrw::Error) -> Self {
+        Self::Binary(error)
+    }
+}
+
+impl From<fmt::Error> for BmgError {
+    fn from(error: fmt::Error) -> Self {
+        Self::Format(error)
+    }
+}
+
+impl From<MarkupError> for BmgError {
+    fn from(error: MarkupError) -> Self {
+        Self::Markup(error)
+    }
+}
+
+impl From<ProjectError> for BmgError {
+    fn from(error: ProjectError) -> Self {
+        Self::Project(error)
+    }
+}
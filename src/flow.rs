@@ -0,0 +1,889 @@
+//! Conversation flows stored in FLW1.
+
+use crate::bmg::*;
+use crate::error::BmgError;
+use crate::markup::TextSegment;
+use crate::profile::Profile;
+use crate::project::{ProjectError, Tags};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Lookup tables over a FLW1 so questions about the whole graph don't have to
+/// rescan every node.
+#[derive(Debug, Default, Clone)]
+pub struct FlowIndex {
+    by_text: HashMap<u16, Vec<u16>>,
+    predecessors: Vec<Vec<u16>>
+}
+
+impl FlowIndex {
+    pub fn new(flw1: &FLW1) -> Result<Self, BmgError> {
+        let mut result = Self {
+            by_text: HashMap::new(),
+            predecessors: vec![vec![]; flw1.entries.len()]
+        };
+        for (i, node) in flw1.entries.iter().enumerate() {
+            if let FLW1Entry::Text(text) = node {
+                result.by_text.entry(text.textid).or_default().push(i as u16);
+            }
+            for next in flw1.successors(i as u16)? {
+                if let Some(predecessors) = result.predecessors.get_mut(next as usize) {
+                    predecessors.push(i as u16);
+                }
+            }
+        }
+        Ok(result)
+    }
+    /// Text nodes that show the message, in node order.
+    #[inline]
+    pub fn nodes_of(&self, textid: u16) -> &[u16] {
+        self.by_text.get(&textid).map_or(&[], Vec::as_slice)
+    }
+    /// Nodes with an edge to this one, through `nexttextid` or the branch table.
+    #[inline]
+    pub fn predecessors(&self, node: u16) -> &[u16] {
+        self.predecessors.get(node as usize).map_or(&[], Vec::as_slice)
+    }
+    /// Whether a flow starts at the message, meaning one of its text nodes is
+    /// only reachable from itself.
+    pub fn has_flow(&self, textid: u16) -> bool {
+        self.nodes_of(textid).iter().any(|&node| self.is_entry(node))
+    }
+    #[inline]
+    pub fn is_entry(&self, node: u16) -> bool {
+        self.predecessors(node).iter().all(|&p| p == node)
+    }
+}
+
+impl FLW1 {
+    #[inline]
+    pub fn index(&self) -> Result<FlowIndex, BmgError> {
+        FlowIndex::new(self)
+    }
+    /// Nodes this node continues to, ends of flows left out.
+    pub fn successors(&self, id: u16) -> Result<Vec<u16>, BmgError> {
+        let next = match self.entries.get(id as usize).ok_or(BmgError::NoSuchNode(id))? {
+            FLW1Entry::Text(text) => vec![text.nexttextid],
+            FLW1Entry::Condition(con) => vec![
+                self.get_branch(id, con.branchnodeid as usize)?,
+                self.get_branch(id, con.branchnodeid as usize + 1)?
+            ],
+            FLW1Entry::Event(eve) if eve.branchnodeid == u16::MAX => vec![],
+            FLW1Entry::Event(eve) => vec![self.get_branch(id, eve.branchnodeid as usize)?]
+        };
+        Ok(next.into_iter().filter(|&next| next != u16::MAX).collect())
+    }
+    /// Nodes where flows start. FLI1 lists them when the file has one,
+    /// otherwise they're guessed from the first `messages` messages.
+    pub fn entry_points(&self, fli1: Option<&FLI1>, messages: usize) -> Result<Vec<u16>, BmgError> {
+        let mut result = vec![];
+        match fli1 {
+            Some(fli1) if !fli1.entries.is_empty() => {
+                result.extend(fli1.entries.iter().map(|entry| entry.node)
+                    .filter(|&node| (node as usize) < self.entries.len()));
+            },
+            _ => {
+                let index = self.index()?;
+                result.extend((0..messages as u16)
+                    .filter_map(|i| index.nodes_of(i).iter().copied().find(|&node| index.is_entry(node))));
+            }
+        }
+        let mut seen = vec![false; self.entries.len()];
+        result.retain(|&node| !std::mem::replace(&mut seen[node as usize], true));
+        Ok(result)
+    }
+    /// Lists every flow as `[beginflow]` blocks of `[node:..]` lines, starting
+    /// with the flows that begin at `entries`. Conditions and events are named
+    /// by the profile, and the nodes FLI1 points at carry its ids.
+    pub fn to_listing(&self, entries: &[u16], fli1: Option<&FLI1>, profile: &Profile) -> Result<String, BmgError> {
+        let flows = fli1.map_or(&[][..], |fli1| &fli1.entries);
+        let mut result = String::new();
+        let mut converted = vec![false; self.entries.len()];
+        for node in entries.iter().copied().chain(0..self.entries.len() as u16) {
+            if node as usize >= self.entries.len() {
+                return Err(BmgError::NoSuchNode(node));
+            }
+            if !converted[node as usize] {
+                let mut txt = String::new();
+                self.write_flow(node, self.entries[node as usize], flows, profile, &mut txt, &mut converted)?;
+                write!(result, "[beginflow]\n{txt}[endflow]\n")?;
+            }
+        }
+        Ok(result)
+    }
+    /// Rebuilds the nodes and the branch table from a listing written by
+    /// [`FLW1::to_listing`]. Nodes may come in any order but their ids must
+    /// cover `0..n`; branch slots are handed out in node order. The FLI1
+    /// entries of the listing come back sorted by id.
+    pub fn from_listing(listing: &str, profile: &Profile) -> Result<(Self, Vec<FLI1Entry>), ProjectError> {
+        let mut nodes: Vec<Option<(usize, ListedNode)>> = vec![];
+        let mut flows: Vec<FLI1Entry> = vec![];
+        let mut last = 0;
+        for (number, line) in listing.lines().enumerate().map(|(i, line)| (i + 1, line.trim())) {
+            last = number;
+            if line.is_empty() || line == "[beginflow]" || line == "[endflow]" {
+                continue;
+            }
+            let error = |reason: String| ProjectError { line: number, reason };
+            let tags = Tags::parse(line).filter(|tags| tags.first() == "node")
+                .ok_or_else(|| error(format!("expected a [node:..] line, found `{line}`")))?;
+            let id: u16 = tags.number("node").map_err(error)?;
+            if id == u16::MAX {
+                return Err(error(format!("node id {id} is reserved")));
+            }
+            let node = read_listed_node(&tags, profile).map_err(error)?;
+            if nodes.len() <= id as usize {
+                nodes.resize(id as usize + 1, None);
+            }
+            if nodes[id as usize].is_some() {
+                return Err(error(format!("node {id} is listed twice")));
+            }
+            nodes[id as usize] = Some((number, node));
+            for flow in tags.values("flowid") {
+                let flow: u32 = flow.parse().map_err(|_| error(format!("bad number `{flow}` in [flowid:..]")))?;
+                if flows.iter().any(|entry| entry.id == flow) {
+                    return Err(error(format!("flow id {flow} is listed twice")));
+                }
+                flows.push(FLI1Entry { id: flow, node: id, padding: 0 });
+            }
+        }
+        flows.sort_by_key(|entry| entry.id);
+        let mut result = Self::default();
+        for (id, node) in nodes.iter().enumerate() {
+            let Some((line, node)) = node else {
+                return Err(ProjectError { line: last, reason: format!("node {id} is missing") });
+            };
+            let targets = match node {
+                ListedNode::Text(_, next) | ListedNode::Event(_, next) => vec![*next],
+                ListedNode::Condition(_, true_id, false_id) => vec![*true_id, *false_id]
+            };
+            if let Some(target) = targets.iter().find(|&&t| t != u16::MAX && t as usize >= nodes.len()) {
+                return Err(ProjectError { line: *line, reason: format!("node {id} points at node {target}, which doesn't exist") });
+            }
+            let branch = result.branch_nodes.len() as u16;
+            result.entries.push(match *node {
+                ListedNode::Text(text, nexttextid) => FLW1Entry::Text(EntryText { nexttextid, ..text }),
+                ListedNode::Condition(con, ..) => FLW1Entry::Condition(EntryCondition { branchnodeid: branch, ..con }),
+                ListedNode::Event(eve, _) => FLW1Entry::Event(EntryEvent { branchnodeid: branch, ..eve })
+            });
+            if !matches!(node, ListedNode::Text(..)) {
+                result.branch_nodes.extend(targets);
+            }
+        }
+        result.nodenum = result.entries.len() as u16;
+        result.branchnodenum = result.branch_nodes.len() as u16;
+        Ok((result, flows))
+    }
+    /// Appends a text node and returns its id.
+    pub fn add_text_node(&mut self, textid: u16, next: Option<u16>) -> Result<u16, BmgError> {
+        let id = self.entries.len() as u16;
+        let nexttextid = self.check_target(id, next)?;
+        self.entries.push(FLW1Entry::Text(EntryText { textid, nexttextid, ..Default::default() }));
+        self.update_counts();
+        Ok(id)
+    }
+    /// Appends a condition node with two fresh branch slots and returns its id.
+    pub fn add_condition(&mut self, conditiontype: u16, arg: u16, true_to: Option<u16>, false_to: Option<u16>) -> Result<u16, BmgError> {
+        let id = self.entries.len() as u16;
+        let targets = [self.check_target(id, true_to)?, self.check_target(id, false_to)?];
+        let branchnodeid = self.branch_nodes.len() as u16;
+        self.branch_nodes.extend(targets);
+        self.entries.push(FLW1Entry::Condition(EntryCondition { unk: 0, conditiontype, arg, branchnodeid }));
+        self.update_counts();
+        Ok(id)
+    }
+    /// Appends an event node with a fresh branch slot and returns its id.
+    pub fn add_event(&mut self, event_type: u8, arg: u32, next: Option<u16>) -> Result<u16, BmgError> {
+        let id = self.entries.len() as u16;
+        let next = self.check_target(id, next)?;
+        let branchnodeid = self.branch_nodes.len() as u16;
+        self.branch_nodes.push(next);
+        self.entries.push(FLW1Entry::Event(EntryEvent { event_type, branchnodeid, arg }));
+        self.update_counts();
+        Ok(id)
+    }
+    /// Removes a node. Edges that pointed at it end the flow instead, later
+    /// nodes move down by one and branch slots nobody else uses are dropped.
+    pub fn remove_node(&mut self, id: u16) -> Result<(), BmgError> {
+        if id as usize >= self.entries.len() {
+            return Err(BmgError::NoSuchNode(id));
+        }
+        let mut slots = self.slots(id);
+        slots.retain(|&slot| self.slot_users(slot) == 1);
+        slots.sort_unstable();
+        for &slot in slots.iter().rev() {
+            self.branch_nodes.remove(slot as usize);
+            for node in &mut self.entries {
+                match node {
+                    FLW1Entry::Condition(EntryCondition { branchnodeid, .. })
+                    | FLW1Entry::Event(EntryEvent { branchnodeid, .. })
+                        if *branchnodeid != u16::MAX && *branchnodeid > slot => *branchnodeid -= 1,
+                    _ => {}
+                }
+            }
+        }
+        self.entries.remove(id as usize);
+        let renumber = |target: &mut u16| match *target {
+            u16::MAX => {},
+            t if t == id => *target = u16::MAX,
+            t if t > id => *target -= 1,
+            _ => {}
+        };
+        for node in &mut self.entries {
+            if let FLW1Entry::Text(text) = node {
+                renumber(&mut text.nexttextid);
+            }
+        }
+        self.branch_nodes.iter_mut().for_each(renumber);
+        self.update_counts();
+        Ok(())
+    }
+    /// Points one edge of a node somewhere else, `None` ends the flow there.
+    /// Branch slots shared with other nodes are copied first so only this
+    /// node changes.
+    pub fn retarget(&mut self, id: u16, edge: Edge, to: Option<u16>) -> Result<(), BmgError> {
+        let target = self.check_target(id, to)?;
+        let node = self.entries.get(id as usize).copied().ok_or(BmgError::NoSuchNode(id))?;
+        let (branchnodeid, count, offset) = match (node, edge) {
+            (FLW1Entry::Text(_), Edge::Next) => {
+                if let FLW1Entry::Text(text) = &mut self.entries[id as usize] {
+                    text.nexttextid = target;
+                }
+                return Ok(());
+            },
+            (FLW1Entry::Condition(con), Edge::True) => (con.branchnodeid, 2, 0),
+            (FLW1Entry::Condition(con), Edge::False) => (con.branchnodeid, 2, 1),
+            (FLW1Entry::Event(eve), Edge::Next) => (eve.branchnodeid, 1, 0),
+            _ => return Err(BmgError::NoSuchEdge { node: id, edge })
+        };
+        let shared = branchnodeid == u16::MAX
+            || self.slots(id).iter().any(|&slot| self.slot_users(slot) > 1);
+        let branchnodeid = if shared {
+            let copy = (0..count).map(|i| match branchnodeid {
+                u16::MAX => Ok(u16::MAX),
+                _ => self.get_branch(id, (branchnodeid + i) as usize)
+            }).collect::<Result<Vec<_>, _>>()?;
+            let new = self.branch_nodes.len() as u16;
+            self.branch_nodes.extend(copy);
+            match &mut self.entries[id as usize] {
+                FLW1Entry::Condition(EntryCondition { branchnodeid, .. })
+                | FLW1Entry::Event(EntryEvent { branchnodeid, .. }) => *branchnodeid = new,
+                FLW1Entry::Text(_) => {}
+            }
+            new
+        } else {
+            branchnodeid
+        };
+        let slot = self.branch_nodes.get_mut((branchnodeid + offset) as usize)
+            .ok_or(BmgError::BadBranch { node: id, branch: branchnodeid + offset })?;
+        *slot = target;
+        self.update_counts();
+        Ok(())
+    }
+    fn check_target(&self, from: u16, to: Option<u16>) -> Result<u16, BmgError> {
+        match to {
+            None => Ok(u16::MAX),
+            Some(to) if (to as usize) < self.entries.len() => Ok(to),
+            Some(to) => Err(BmgError::DanglingFlowRef { node: from, target: to })
+        }
+    }
+    fn slots(&self, id: u16) -> Vec<u16> {
+        match self.entries[id as usize] {
+            FLW1Entry::Condition(con) if con.branchnodeid != u16::MAX => vec![con.branchnodeid, con.branchnodeid + 1],
+            FLW1Entry::Event(eve) if eve.branchnodeid != u16::MAX => vec![eve.branchnodeid],
+            _ => vec![]
+        }
+    }
+    fn slot_users(&self, slot: u16) -> usize {
+        (0..self.entries.len() as u16).filter(|&id| self.slots(id).contains(&slot)).count()
+    }
+    fn update_counts(&mut self) {
+        self.nodenum = self.entries.len() as u16;
+        self.branchnodenum = self.branch_nodes.len() as u16;
+    }
+    /// Checks the graph for broken references and dead ends, given the number
+    /// of messages in INF1 and the file's FLI1. Without FLI1 every text node
+    /// nothing points at counts as the start of a flow.
+    pub fn validate(&self, messages: usize, fli1: Option<&FLI1>) -> Vec<FlowProblem> {
+        let mut problems = vec![];
+        let nodes = self.entries.len();
+        let fli1 = fli1.filter(|fli1| !fli1.entries.is_empty());
+        for entry in fli1.iter().flat_map(|fli1| &fli1.entries) {
+            if entry.node as usize >= nodes {
+                problems.push(FlowProblem::DanglingEntry { flow: entry.id, node: entry.node });
+            }
+        }
+        let mut used = vec![false; self.branch_nodes.len()];
+        // Every edge of every node, u16::MAX and dangling targets included.
+        let mut edges = vec![vec![]; nodes];
+        for (i, node) in self.entries.iter().enumerate() {
+            let id = i as u16;
+            let slots = match node {
+                FLW1Entry::Text(text) => {
+                    if text.textid as usize >= messages {
+                        problems.push(FlowProblem::MissingMessage { node: id, message: text.textid });
+                    }
+                    edges[i].push(text.nexttextid);
+                    0..0
+                },
+                FLW1Entry::Condition(con) => con.branchnodeid as usize..(con.branchnodeid as usize + 2),
+                FLW1Entry::Event(eve) if eve.branchnodeid == u16::MAX => {
+                    edges[i].push(u16::MAX);
+                    0..0
+                },
+                FLW1Entry::Event(eve) => eve.branchnodeid as usize..(eve.branchnodeid as usize + 1)
+            };
+            for slot in slots {
+                match self.branch_nodes.get(slot) {
+                    Some(&target) => {
+                        used[slot] = true;
+                        edges[i].push(target);
+                    },
+                    None => problems.push(FlowProblem::BadBranch { node: id, branch: slot as u16 })
+                }
+            }
+            for &target in &edges[i] {
+                if target != u16::MAX && target as usize >= nodes {
+                    problems.push(FlowProblem::DanglingRef { node: id, target });
+                }
+            }
+        }
+        let successors = edges.iter()
+            .map(|edges| edges.iter().copied().filter(|&t| (t as usize) < nodes).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        for component in components(&successors) {
+            let looped = component.len() > 1 || successors[component[0] as usize].contains(&component[0]);
+            // Ending the flow or leaving through a broken edge both count as a way out.
+            let exit = component.iter().any(|&node| edges[node as usize].iter()
+                .any(|t| (*t as usize) >= nodes || !component.contains(t)));
+            if looped && !exit {
+                let mut nodes = component;
+                nodes.sort_unstable();
+                problems.push(FlowProblem::NoExit { nodes });
+            }
+        }
+        let mut predecessors = vec![0usize; nodes];
+        for (i, successors) in successors.iter().enumerate() {
+            for &next in successors {
+                if next as usize != i {
+                    predecessors[next as usize] += 1;
+                }
+            }
+        }
+        let mut reached = vec![false; nodes];
+        let mut queue = match fli1 {
+            Some(fli1) => fli1.entries.iter().map(|entry| entry.node as usize).filter(|&node| node < nodes).collect(),
+            None => (0..nodes)
+                .filter(|&i| predecessors[i] == 0 && matches!(self.entries[i], FLW1Entry::Text(_)))
+                .collect::<Vec<_>>()
+        };
+        while let Some(node) = queue.pop() {
+            if !std::mem::replace(&mut reached[node], true) {
+                queue.extend(successors[node].iter().map(|&next| next as usize));
+            }
+        }
+        problems.extend((0..nodes).filter(|&i| !reached[i]).map(|i| FlowProblem::Unreachable { node: i as u16 }));
+        problems.extend((0..used.len()).filter(|&i| !used[i]).map(|i| FlowProblem::UnusedBranch { slot: i as u16 }));
+        problems
+    }
+    /// Writes the whole flow graph as a Graphviz DOT file. Text nodes are
+    /// labelled with their message as the profile decodes it, `[newline]`
+    /// becomes a line break.
+    pub fn to_dot(&self, inf1: &INF1, dat1: &DAT1, profile: &Profile) -> Result<String, BmgError> {
+        let mut result = String::from("digraph flow {\n    node [shape=box];\n");
+        for (i, node) in self.entries.iter().enumerate() {
+            let id = i as u16;
+            match node {
+                FLW1Entry::Text(text) => {
+                    let label = match inf1.entries.get(text.textid as usize) {
+                        Some(_) => profile.get_segments(inf1, text.textid as usize, dat1)?.iter()
+                            .map(|segment| match segment {
+                                TextSegment::NewLine => String::from("\n"),
+                                _ => profile.render(std::slice::from_ref(segment))
+                            }).collect(),
+                        None => String::from("(no such message)")
+                    };
+                    let label = format!("{id}: message {}\n{label}", text.textid);
+                    writeln!(result, "    n{id} [label=\"{}\"];", escape(&label))?;
+                    write_edge(&mut result, id, text.nexttextid, None)?;
+                },
+                FLW1Entry::Condition(con) => {
+                    let arg = profile.value_name(profile.choices(con.conditiontype), con.arg as u32);
+                    let label = format!("{id}: {}\n{arg}", profile.condition_name(con.conditiontype));
+                    writeln!(result, "    n{id} [shape=diamond, label=\"{}\"];", escape(&label))?;
+                    let true_id = self.get_branch(id, con.branchnodeid as usize)?;
+                    let false_id = self.get_branch(id, con.branchnodeid as usize + 1)?;
+                    write_edge(&mut result, id, true_id, Some("true"))?;
+                    write_edge(&mut result, id, false_id, Some("false"))?;
+                },
+                FLW1Entry::Event(eve) => {
+                    let label = format!("{id}: {}\n{}", profile.event_name(eve.event_type), eve.arg);
+                    writeln!(result, "    n{id} [shape=ellipse, label=\"{}\"];", escape(&label))?;
+                    if eve.branchnodeid != u16::MAX {
+                        write_edge(&mut result, id, self.get_branch(id, eve.branchnodeid as usize)?, None)?;
+                    }
+                }
+            }
+        }
+        result += "}\n";
+        Ok(result)
+    }
+}
+
+fn write_edge(result: &mut String, from: u16, to: u16, label: Option<&str>) -> std::fmt::Result {
+    match (to, label) {
+        (u16::MAX, _) => Ok(()),
+        (to, Some(label)) => writeln!(result, "    n{from} -> n{to} [label=\"{label}\"];"),
+        (to, None) => writeln!(result, "    n{from} -> n{to};")
+    }
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Steps through a flow one node at a time, the caller decides how each
+/// condition turns out.
+#[derive(Debug, Clone)]
+pub struct FlowWalker<'a> {
+    flw1: &'a FLW1,
+    node: Option<u16>
+}
+
+impl<'a> FlowWalker<'a> {
+    pub fn new(flw1: &'a FLW1, node: u16) -> Result<Self, BmgError> {
+        if node as usize >= flw1.entries.len() {
+            return Err(BmgError::NoSuchNode(node));
+        }
+        Ok(Self { flw1, node: Some(node) })
+    }
+    /// Starts at the entry point of a flow that begins with the message, if
+    /// there is one. Entry points come from FLI1, see [`FLW1::entry_points`].
+    pub fn from_message(flw1: &'a FLW1, fli1: Option<&FLI1>, textid: u16) -> Result<Option<Self>, BmgError> {
+        let node = flw1.entry_points(fli1, textid as usize + 1)?.into_iter()
+            .find(|&node| matches!(flw1.entries[node as usize], FLW1Entry::Text(text) if text.textid == textid));
+        Ok(node.map(|node| Self { flw1, node: Some(node) }))
+    }
+    /// The current node, `None` once the flow has ended.
+    #[inline]
+    pub fn node(&self) -> Option<u16> {
+        self.node
+    }
+    #[inline]
+    pub fn entry(&self) -> Option<FLW1Entry> {
+        self.node.map(|node| self.flw1.entries[node as usize])
+    }
+    /// Moves to the next node. `outcome` picks the branch of a condition and
+    /// is ignored everywhere else.
+    pub fn advance(&mut self, outcome: bool) -> Result<Option<FLW1Entry>, BmgError> {
+        let Some(node) = self.node else {
+            return Ok(None);
+        };
+        let next = match self.flw1.entries[node as usize] {
+            FLW1Entry::Text(text) => text.nexttextid,
+            FLW1Entry::Condition(con) => {
+                let branch = con.branchnodeid as usize + if outcome { 0 } else { 1 };
+                self.flw1.get_branch(node, branch)?
+            },
+            FLW1Entry::Event(eve) if eve.branchnodeid == u16::MAX => u16::MAX,
+            FLW1Entry::Event(eve) => self.flw1.get_branch(node, eve.branchnodeid as usize)?
+        };
+        self.node = match next {
+            u16::MAX => None,
+            next => {
+                self.flw1.get_node(node, next)?;
+                Some(next)
+            }
+        };
+        Ok(self.entry())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlowProblem {
+    DanglingRef { node: u16, target: u16 },
+    BadBranch { node: u16, branch: u16 },
+    MissingMessage { node: u16, message: u16 },
+    DanglingEntry { flow: u32, node: u16 },
+    NoExit { nodes: Vec<u16> },
+    Unreachable { node: u16 },
+    UnusedBranch { slot: u16 }
+}
+
+impl std::fmt::Display for FlowProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DanglingRef { node, target } => write!(f, "node {node} points at node {target}, which doesn't exist"),
+            Self::BadBranch { node, branch } => write!(f, "node {node} uses branch slot {branch}, which doesn't exist"),
+            Self::MissingMessage { node, message } => write!(f, "node {node} shows message {message}, which doesn't exist"),
+            Self::DanglingEntry { flow, node } => write!(f, "FLI1 starts flow {flow} at node {node}, which doesn't exist"),
+            Self::NoExit { nodes } if nodes.len() == 1 => write!(f, "node {} loops without a way out", nodes[0]),
+            Self::NoExit { nodes } => {
+                let nodes = nodes.iter().map(u16::to_string).collect::<Vec<_>>().join(", ");
+                write!(f, "nodes {nodes} loop without a way out")
+            },
+            Self::Unreachable { node } => write!(f, "node {node} can't be reached from any flow entry"),
+            Self::UnusedBranch { slot } => write!(f, "branch slot {slot} isn't used by any node")
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    Next,
+    True,
+    False
+}
+
+#[derive(Clone, Copy)]
+enum ListedNode {
+    Text(EntryText, u16),
+    Condition(EntryCondition, u16, u16),
+    Event(EntryEvent, u16)
+}
+
+fn read_listed_node(tags: &Tags, profile: &Profile) -> Result<ListedNode, String> {
+    let target = |keys: &[&str]| {
+        let value = keys.iter().find_map(|key| tags.get(key).ok())
+            .ok_or_else(|| format!("missing [{}:..]", keys[0]))?;
+        match value {
+            "none" => Ok(u16::MAX),
+            _ => value.parse().map_err(|_| format!("bad node `{value}` in [{}:..]", keys[0]))
+        }
+    };
+    let optional = |key: &str| if tags.has(key) { tags.number(key) } else { Ok(0) };
+    // Older listings wrote `[type:condition][type:Name]`.
+    let name = |key: &str| tags.get(key).or_else(|e| tags.values("type").nth(1).ok_or(e));
+    match tags.get("type")? {
+        "text" => Ok(ListedNode::Text(EntryText {
+            unk: optional("unk")?,
+            textid: tags.number("messageid")?,
+            nexttextid: 0,
+            validity: optional("validity")?,
+            unk2: optional("unk2")?
+        }, target(&["next"])?)),
+        "condition" => {
+            let name = name("condition")?;
+            let conditiontype = profile.condition_type(name)
+                .ok_or_else(|| format!("unknown condition `{name}`"))?;
+            let arg = match tags.get("choice") {
+                Ok(choice) => profile.value(profile.choices(conditiontype), choice)
+                    .and_then(|choice| choice.try_into().ok())
+                    .ok_or_else(|| format!("unknown choice `{choice}`"))?,
+                Err(_) => tags.number("arg")?
+            };
+            let con = EntryCondition { unk: optional("unk")?, conditiontype, arg, branchnodeid: 0 };
+            Ok(ListedNode::Condition(con, target(&["true", "trueflow"])?, target(&["false", "falseflow"])?))
+        },
+        "event" => {
+            let name = name("event")?;
+            let event_type = profile.event_type(name)
+                .ok_or_else(|| format!("unknown event `{name}`"))?;
+            let eve = EntryEvent { event_type, branchnodeid: 0, arg: tags.number("arg")? };
+            Ok(ListedNode::Event(eve, target(&["next"])?))
+        },
+        other => Err(format!("unknown node type `{other}`"))
+    }
+}
+
+/// Strongly connected components of a graph given as successor lists, found
+/// with Tarjan's algorithm without recursion so long flows can't overflow the stack.
+fn components(successors: &[Vec<u16>]) -> Vec<Vec<u16>> {
+    let nodes = successors.len();
+    let mut index = vec![usize::MAX; nodes];
+    let mut low = vec![0; nodes];
+    let mut on_stack = vec![false; nodes];
+    let mut stack = vec![];
+    let mut result = vec![];
+    let mut counter = 0;
+    for root in 0..nodes {
+        if index[root] != usize::MAX {
+            continue;
+        }
+        let mut work = vec![(root, 0)];
+        while let Some(&(node, next)) = work.last() {
+            if index[node] == usize::MAX {
+                index[node] = counter;
+                low[node] = counter;
+                counter += 1;
+                stack.push(node);
+                on_stack[node] = true;
+            }
+            if let Some(&target) = successors[node].get(next) {
+                let target = target as usize;
+                work.last_mut().unwrap().1 += 1;
+                if index[target] == usize::MAX {
+                    work.push((target, 0));
+                } else if on_stack[target] {
+                    low[node] = low[node].min(index[target]);
+                }
+                continue;
+            }
+            work.pop();
+            if let Some(&(parent, _)) = work.last() {
+                low[parent] = low[parent].min(low[node]);
+            }
+            if low[node] == index[node] {
+                let mut component = vec![];
+                while let Some(member) = stack.pop() {
+                    on_stack[member] = false;
+                    component.push(member as u16);
+                    if member == node {
+                        break;
+                    }
+                }
+                result.push(component);
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::bmg::tests::SAMPLE;
+
+    /// Message 0 leads to a choice, the first answer fires an event before
+    /// message 3 and the second shows message 2.
+    pub(crate) fn sample() -> FLW1 {
+        let mut flw1 = FLW1::default();
+        let end = flw1.add_text_node(3, None).unwrap();
+        let event = flw1.add_event(4, 3, Some(end)).unwrap();
+        let other = flw1.add_text_node(2, None).unwrap();
+        let choice = flw1.add_condition(0, 0, Some(event), Some(other)).unwrap();
+        let question = flw1.add_text_node(1, Some(choice)).unwrap();
+        flw1.add_text_node(0, Some(question)).unwrap();
+        flw1
+    }
+
+    fn shares_slot_0(flw1: &mut FLW1) -> u16 {
+        flw1.entries.push(FLW1Entry::Event(EntryEvent { event_type: 5, branchnodeid: 0, arg: 0 }));
+        flw1.update_counts();
+        flw1.entries.len() as u16 - 1
+    }
+
+    #[test]
+    fn dot_export() {
+        let bmg = BMG::from_bytes(&SAMPLE).unwrap();
+        let (inf1, dat1) = (bmg.get_inf1().unwrap(), bmg.get_dat1().unwrap());
+        let dot = bmg.get_flw1().unwrap().to_dot(inf1, dat1, &bmg.profile).unwrap();
+        assert_eq!(dot, concat!(
+            "digraph flow {\n",
+            "    node [shape=box];\n",
+            "    n0 [label=\"0: message 0\\nHi\"];\n",
+            "    n0 -> n1;\n",
+            "    n1 [shape=diamond, label=\"1: MultipleChoice\\nPenguinRace\"];\n",
+            "    n1 -> n3 [label=\"true\"];\n",
+            "    n1 -> n2 [label=\"false\"];\n",
+            "    n2 [label=\"2: message 1\\nB\"];\n",
+            "    n3 [shape=ellipse, label=\"3: NpcEventAlt\\n3\"];\n",
+            "    n3 -> n2;\n",
+            "}\n"
+        ));
+        assert_eq!(escape("a \"b\"\\\nc"), "a \\\"b\\\"\\\\\\nc");
+    }
+
+    #[test]
+    fn listing_round_trip() {
+        let bmg = BMG::from_bytes(&SAMPLE).unwrap();
+        let flw1 = bmg.get_flw1().unwrap();
+        let listing = flw1.to_listing(&flw1.entry_points(None, 2).unwrap(), None, &bmg.profile).unwrap();
+        assert!(listing.contains("[node:1][type:condition][condition:MultipleChoice][choice:PenguinRace][true:3][false:2]"));
+        let (read, flows) = FLW1::from_listing(&listing, &bmg.profile).unwrap();
+        assert_eq!(read.to_listing(&read.entry_points(None, 2).unwrap(), None, &bmg.profile).unwrap(), listing);
+        assert_eq!(read.branch_nodes, flw1.branch_nodes);
+        assert_eq!((read.nodenum, read.branchnodenum), (4, 3));
+        assert!(flows.is_empty());
+    }
+
+    #[test]
+    fn listing_keeps_flow_ids() {
+        let bmg = BMG::from_bytes(&SAMPLE).unwrap();
+        let (flw1, fli1) = (bmg.get_flw1().unwrap(), bmg.get_fli1());
+        let listing = flw1.to_listing(&flw1.entry_points(fli1, 2).unwrap(), fli1, &bmg.profile).unwrap();
+        assert!(listing.starts_with("[beginflow]\n[node:0][type:text][messageid:0][next:1][flowid:7]\n"));
+        let (_, flows) = FLW1::from_listing(&listing.replace("[flowid:7]", "[flowid:9]"), &bmg.profile).unwrap();
+        assert_eq!(flows, [FLI1Entry { id: 9, node: 0, padding: 0 }]);
+        let listing = listing.replace("[next:none]", "[next:none][flowid:9]");
+        let error = FLW1::from_listing(&listing.replace("[flowid:7]", "[flowid:9]"), &bmg.profile).unwrap_err();
+        assert_eq!(error.reason, "flow id 9 is listed twice");
+        let (_, flows) = FLW1::from_listing(&listing, &bmg.profile).unwrap();
+        assert_eq!(flows.iter().map(|flow| flow.id).collect::<Vec<_>>(), [7, 9]);
+    }
+
+    #[test]
+    fn listing_long_chains() {
+        let entries = (0..60000).map(|i| FLW1Entry::Text(EntryText {
+            textid: 0,
+            nexttextid: if i < 59999 { i + 1 } else { u16::MAX },
+            ..Default::default()
+        })).collect();
+        let flw1 = FLW1 { entries, ..Default::default() };
+        let listing = flw1.to_listing(&[0], None, &Profile::default()).unwrap();
+        assert_eq!(listing.lines().count(), 60002);
+        assert!(listing.contains("[node:59999][type:text][messageid:0][next:none]\n[endflow]"));
+    }
+
+    #[test]
+    fn listing_assigns_branch_slots() {
+        let listing = "[beginflow]
+[node:0][type:text][messageid:0][next:1]
+[node:1][type:condition][condition:MultipleChoice][choice:PenguinRace][true:2][false:3]
+[node:2][type:event][event:NpcEventAlt][arg:3][next:3]
+[node:3][type:text][messageid:1][next:none]
+[endflow]
+";
+        let profile = Profile::default();
+        let (flw1, _) = FLW1::from_listing(listing, &profile).unwrap();
+        assert_eq!(flw1.branch_nodes, [2, 3, 3]);
+        assert!(matches!(flw1.entries[2], FLW1Entry::Event(EntryEvent { branchnodeid: 2, arg: 3, .. })));
+        assert!(FLW1::from_listing(&listing.replace("[true:2]", "[true:9]"), &profile).is_err());
+    }
+
+    #[test]
+    fn editing_keeps_the_branch_table() {
+        let flw1 = sample();
+        assert_eq!(flw1.branch_nodes, [0, 1, 2]);
+        assert_eq!((flw1.nodenum, flw1.branchnodenum), (6, 3));
+        let listing = flw1.to_listing(&flw1.entry_points(None, 5).unwrap(), None, &Profile::default()).unwrap();
+        assert!(listing.contains("[node:3][type:condition][condition:MultipleChoice][choice:PenguinRace][true:1][false:2]"));
+        assert!(listing.contains("[node:1][type:event][event:Emotion][arg:3][next:0]"));
+    }
+
+    #[test]
+    fn remove_node_drops_its_slots() {
+        let mut flw1 = sample();
+        flw1.remove_node(3).unwrap();
+        assert_eq!(flw1.branch_nodes, [0]);
+        assert!(matches!(flw1.entries[3], FLW1Entry::Text(EntryText { textid: 1, nexttextid: u16::MAX, .. })));
+        assert_eq!((flw1.nodenum, flw1.branchnodenum), (5, 1));
+    }
+
+    #[test]
+    fn remove_node_renumbers_targets() {
+        let mut flw1 = sample();
+        flw1.remove_node(0).unwrap();
+        assert_eq!(flw1.branch_nodes, [u16::MAX, 0, 1]);
+        assert!(matches!(flw1.entries[3], FLW1Entry::Text(EntryText { textid: 1, nexttextid: 2, .. })));
+    }
+
+    #[test]
+    fn remove_node_keeps_shared_slots() {
+        let mut flw1 = sample();
+        shares_slot_0(&mut flw1);
+        flw1.remove_node(1).unwrap();
+        assert_eq!(flw1.branch_nodes, [0, u16::MAX, 1]);
+        assert!(matches!(flw1.entries[5], FLW1Entry::Event(EntryEvent { branchnodeid: 0, .. })));
+    }
+
+    #[test]
+    fn retarget_copies_shared_slots() {
+        let mut flw1 = sample();
+        let shared = shares_slot_0(&mut flw1);
+        flw1.retarget(shared, Edge::Next, Some(2)).unwrap();
+        assert_eq!(flw1.branch_nodes, [0, 1, 2, 2]);
+        assert!(matches!(flw1.entries[shared as usize], FLW1Entry::Event(EntryEvent { branchnodeid: 3, .. })));
+        flw1.retarget(3, Edge::False, None).unwrap();
+        assert_eq!(flw1.branch_nodes, [0, 1, u16::MAX, 2]);
+        assert_eq!(flw1.branchnodenum, 4);
+        assert!(flw1.retarget(3, Edge::Next, None).is_err());
+    }
+
+    #[test]
+    fn index_finds_entries_and_predecessors() {
+        let mut flw1 = sample();
+        let index = flw1.index().unwrap();
+        assert_eq!(index.nodes_of(1), [4]);
+        assert_eq!(index.nodes_of(9), []);
+        assert_eq!(index.predecessors(0), [1]);
+        assert_eq!(index.predecessors(2), [3]);
+        assert_eq!(index.predecessors(3), [4]);
+        assert!(index.has_flow(0));
+        assert!(!index.has_flow(1));
+        assert!(!index.has_flow(9));
+        assert!(index.is_entry(5));
+        assert!(!index.is_entry(4));
+        // A node that only loops back to itself still starts a flow.
+        let lonely = flw1.add_text_node(7, None).unwrap();
+        flw1.retarget(lonely, Edge::Next, Some(lonely)).unwrap();
+        let index = flw1.index().unwrap();
+        assert_eq!(index.predecessors(lonely), [lonely]);
+        assert!(index.has_flow(7));
+    }
+
+    #[test]
+    fn entry_points_prefer_fli1() {
+        let flw1 = sample();
+        assert_eq!(flw1.entry_points(None, 4).unwrap(), [5]);
+        let entry = |id, node| FLI1Entry { id, node, padding: 0 };
+        let mut fli1 = FLI1 { entrylength: 8, entries: vec![entry(7, 2), entry(8, 2), entry(9, 40)], ..Default::default() };
+        assert_eq!(flw1.entry_points(Some(&fli1), 4).unwrap(), [2]);
+        assert_eq!(flw1.validate(4, Some(&fli1)), [
+            FlowProblem::DanglingEntry { flow: 9, node: 40 },
+            FlowProblem::Unreachable { node: 0 },
+            FlowProblem::Unreachable { node: 1 },
+            FlowProblem::Unreachable { node: 3 },
+            FlowProblem::Unreachable { node: 4 },
+            FlowProblem::Unreachable { node: 5 }
+        ]);
+        fli1.entries.clear();
+        assert_eq!(flw1.entry_points(Some(&fli1), 4).unwrap(), [5]);
+    }
+
+    #[test]
+    fn validate_finds_problems() {
+        let mut flw1 = sample();
+        assert_eq!(flw1.validate(4, None), []);
+        assert_eq!(flw1.validate(3, None), [FlowProblem::MissingMessage { node: 0, message: 3 }]);
+        let first = flw1.add_text_node(4, None).unwrap();
+        let second = flw1.add_text_node(5, Some(first)).unwrap();
+        flw1.retarget(first, Edge::Next, Some(second)).unwrap();
+        flw1.branch_nodes.push(0);
+        assert_eq!(flw1.validate(6, None), [
+            FlowProblem::NoExit { nodes: vec![first, second] },
+            FlowProblem::Unreachable { node: first },
+            FlowProblem::Unreachable { node: second },
+            FlowProblem::UnusedBranch { slot: 3 }
+        ]);
+    }
+
+    #[test]
+    fn components_groups_cycles() {
+        let successors = vec![vec![1], vec![2], vec![0, 3], vec![3], vec![]];
+        let mut found = components(&successors).into_iter()
+            .map(|mut component| { component.sort_unstable(); component })
+            .collect::<Vec<_>>();
+        found.sort();
+        assert_eq!(found, [vec![0, 1, 2], vec![3], vec![4]]);
+        let chain = (0..60000).map(|i| if i < 59999 { vec![i + 1] } else { vec![] }).collect::<Vec<_>>();
+        assert_eq!(components(&chain).len(), 60000);
+    }
+
+    #[test]
+    fn walker_follows_outcomes() {
+        let flw1 = sample();
+        let path = |outcome: bool| {
+            let mut walker = FlowWalker::from_message(&flw1, None, 0).unwrap().unwrap();
+            let mut nodes = vec![walker.node().unwrap()];
+            while walker.advance(outcome).unwrap().is_some() {
+                nodes.push(walker.node().unwrap());
+            }
+            assert_eq!((walker.node(), walker.entry().is_none()), (None, true));
+            nodes
+        };
+        assert_eq!(path(true), [5, 4, 3, 1, 0]);
+        assert_eq!(path(false), [5, 4, 3, 2]);
+        assert!(FlowWalker::from_message(&flw1, None, 1).unwrap().is_none());
+        // With FLI1 only the flows it lists start anywhere.
+        let fli1 = FLI1 { entrylength: 8, entries: vec![FLI1Entry { id: 1, node: 2, padding: 0 }], ..Default::default() };
+        assert_eq!(FlowWalker::from_message(&flw1, Some(&fli1), 2).unwrap().unwrap().node(), Some(2));
+        assert!(FlowWalker::from_message(&flw1, Some(&fli1), 0).unwrap().is_none());
+        assert!(FlowWalker::new(&flw1, 6).is_err());
+        let mut walker = FlowWalker::new(&flw1, 0).unwrap();
+        assert!(walker.advance(true).unwrap().is_none());
+        assert!(walker.advance(true).unwrap().is_none());
+    }
+}
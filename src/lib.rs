@@ -0,0 +1,22 @@
+//! Reading, editing and writing of BMG message files, from Super Mario Galaxy
+//! and other games that share the format.
+
+#![allow(clippy::upper_case_acronyms)]
+
+pub mod bmg;
+pub mod encoding;
+pub mod error;
+pub mod flow;
+pub mod markup;
+pub mod profile;
+pub mod project;
+pub mod scenario;
+mod util;
+
+pub use binrw::Endian;
+pub use bmg::{BMG, DAT1, FLI1, FLI1Entry, FLW1, FLW1Entry, GalaxyInfo, INF1, INF1Entry, MID1, Section, TwilightInfo, WindWakerInfo};
+pub use encoding::Encoding;
+pub use error::BmgError;
+pub use flow::{Edge, FlowIndex, FlowWalker};
+pub use profile::Profile;
+pub use util::{Condition, Event};
@@ -1,40 +1,473 @@
-#![allow(dead_code)]
-
-mod bmg;
-mod font;
-mod util;
-
-use bmg::BMG;
-use binrw::prelude::*;
-use std::io::Cursor;
-
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut data = Cursor::new(std::fs::read("Message.bmg")?);
-    let bmg = BMG::read(&mut data)?;
-    let inf1 = bmg.get_inf1().unwrap();
-    let dat1 = bmg.get_dat1().unwrap();
-    let flw1 = bmg.get_flw1().unwrap();
-    let mut converted = vec![false; flw1.nodenum as usize];
-    for i in 0..inf1.entrynum as usize {
-        let entry = inf1.entries[i];
-        println!("{}", entry.get_info());
-        if entry.has_text(dat1) {
-            println!("{}", inf1.get_string(i, dat1))
-        }
-        if flw1.has_flow(i as u16) {
-            let txt = flw1.findnode(i as u16, &mut converted)?;
-            if !txt.is_empty() {
-                println!("[beginflow]\n{txt}[endflow]")
+use yetanothertexttool::bmg::SectionMagic;
+use yetanothertexttool::{project, scenario, BmgError, Encoding, Endian, BMG, DAT1, FLI1, FLW1, FLW1Entry, FlowWalker, INF1, Profile, Section};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: yetanothertexttool <command> [options]
+
+commands:
+  extract <bmg> [-o <project>]   write a BMG as a text project
+  build <project> [-o <bmg>] [--encoding <encoding>]
+                                 build a BMG from a text project
+  info <bmg>                     print the header and the sections of a BMG
+  validate <bmg>                 check that a BMG rebuilds byte for byte and
+                                 that its flows are sound
+  flow [list] <bmg> [-o <file>]  print the conversation flows of a BMG
+  flow graph <bmg> [-o <dot>]    write the flows as a Graphviz DOT graph
+  flow apply <bmg> <listing> [-o <bmg>]
+                                 replace the flows of a BMG with a listing,
+                                 FLI1 takes the [flowid:..] of its nodes
+  simulate <bmg> --message <id> | --node <id>
+                                 click through a conversation, answering
+                                 conditions on stdin
+  test-flows <bmg> <scenarios>   run scripted flow scenarios against a BMG
+  convert <bmg> [-o <bmg>] [--endian big|little|swap] [--encoding <encoding>]
+                                 read a BMG and write it again, optionally
+                                 in another byte order or text encoding
+  profile <game> [-o <file>]     write the definition of a game profile, as a
+                                 start for a profile of your own
+
+Commands that read a BMG and `build` take `--profile <game>` to pick the game
+instead of detecting it from the file: smg1, mkw, ww, tp, sms, lm or
+the path of a profile definition. Encodings are cp1252, utf16, shift-jis and
+utf8.
+
+Paths default to stdin and stdout, `-` selects them explicitly.";
+
+struct Args {
+    command: String,
+    positional: Vec<String>,
+    options: HashMap<String, String>
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let command = args.next().ok_or("missing command")?;
+        let mut positional = vec![];
+        let mut options = HashMap::new();
+        while let Some(arg) = args.next() {
+            let name = match arg.as_str() {
+                "-o" | "--output" => "output",
+                "-" => {
+                    positional.push(arg);
+                    continue;
+                },
+                _ => match arg.strip_prefix("--") {
+                    Some(name) => name,
+                    None if arg.starts_with('-') => return Err(format!("unknown option `{arg}`")),
+                    None => {
+                        positional.push(arg);
+                        continue;
+                    }
+                }
+            };
+            let value = args.next().ok_or_else(|| format!("missing value for `{arg}`"))?;
+            options.insert(name.to_string(), value);
+        }
+        Ok(Self { command, positional, options })
+    }
+    fn input(&self) -> Result<Option<&str>, Usage> {
+        match &self.positional[..] {
+            [] => Ok(None),
+            [input] => Ok(Some(input)),
+            [_, extra, ..] => Err(Usage(format!("unexpected argument `{extra}`")))
+        }
+    }
+    fn output(&self) -> Option<&str> {
+        self.options.get("output").map(String::as_str)
+    }
+    fn check_options(&self, allowed: &[&str]) -> Result<(), Usage> {
+        match self.options.keys().find(|k| !allowed.contains(&k.as_str())) {
+            Some(option) => Err(Usage(format!("`{}` does not take `--{option}`", self.command))),
+            None => Ok(())
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    let result = match args.command.as_str() {
+        "extract" => extract(&args),
+        "build" => build(&args),
+        "info" => info(&args),
+        "validate" => validate(&args),
+        "flow" => flow(&args),
+        "simulate" => simulate(&args),
+        "test-flows" => test_flows(&args),
+        "convert" => convert(&args),
+        "profile" => profile(&args),
+        "help" | "-h" | "--help" => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        },
+        other => {
+            eprintln!("error: unknown command `{other}`\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match result {
+        Ok(code) => code,
+        Err(Failure::Usage(message)) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            ExitCode::from(2)
+        },
+        Err(Failure::Error(error)) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+enum Failure {
+    Usage(String),
+    Error(Box<dyn Error>)
+}
+
+struct Usage(String);
+
+impl From<Usage> for Failure {
+    fn from(Usage(message): Usage) -> Self {
+        Self::Usage(message)
+    }
+}
+
+impl<E: Error + 'static> From<E> for Failure {
+    fn from(error: E) -> Self {
+        Self::Error(Box::new(error))
+    }
+}
+
+type CommandResult = Result<ExitCode, Failure>;
+
+fn read_input(path: Option<&str>) -> std::io::Result<Vec<u8>> {
+    match path {
+        None | Some("-") => {
+            let mut data = vec![];
+            std::io::stdin().read_to_end(&mut data)?;
+            Ok(data)
+        },
+        Some(path) => std::fs::read(path)
+    }
+}
+
+fn write_output(path: Option<&str>, data: &[u8]) -> std::io::Result<()> {
+    match path {
+        None | Some("-") => std::io::stdout().write_all(data),
+        Some(path) => std::fs::write(path, data)
+    }
+}
+
+/// A built-in profile by name, or else a profile definition by path.
+fn load_profile(name: &str) -> Result<Profile, Failure> {
+    if let Some(profile) = Profile::builtin(name) {
+        return Ok(profile);
+    }
+    let text = match std::fs::read_to_string(name) {
+        Ok(text) => text,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Err(Usage(format!("unknown profile `{name}`")).into()),
+        Err(error) => return Err(error.into())
+    };
+    Profile::from_definition(&text).map_err(|error| Failure::Error(format!("{name}: {error}").into()))
+}
+
+fn read_bmg(args: &Args, path: Option<&str>) -> Result<(BMG, Vec<u8>), Failure> {
+    let profile = args.options.get("profile").map(|name| load_profile(name)).transpose()?;
+    let data = read_input(path)?;
+    let bmg = BMG::from_bytes_with(&data, profile)?;
+    Ok((bmg, data))
+}
+
+fn extract(args: &Args) -> CommandResult {
+    args.check_options(&["output", "profile"])?;
+    let (bmg, _) = read_bmg(args, args.input()?)?;
+    let text = project::export(&bmg)?;
+    write_output(args.output(), text.as_bytes())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn build(args: &Args) -> CommandResult {
+    args.check_options(&["output", "encoding", "profile"])?;
+    let profile = args.options.get("profile").map(|name| load_profile(name)).transpose()?;
+    let text = String::from_utf8(read_input(args.input()?)?)?;
+    let mut bmg = project::import_with(&text, profile)?;
+    set_encoding(args, &mut bmg)?;
+    write_output(args.output(), &bmg.to_bytes()?)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn info(args: &Args) -> CommandResult {
+    args.check_options(&["profile"])?;
+    let (bmg, data) = read_bmg(args, args.input()?)?;
+    let header = bmg.header;
+    let mut result = String::new();
+    writeln!(result, "magic: {}", String::from_utf8_lossy(&header.magic))?;
+    writeln!(result, "endian: {:?}", header.endian())?;
+    writeln!(result, "profile: {}", bmg.profile.name)?;
+    writeln!(result, "encoding: {:?}", bmg.encoding())?;
+    writeln!(result, "size: {}", data.len())?;
+    writeln!(result, "filesize: {}", header.filesize)?;
+    writeln!(result, "sections: {}", header.sectioncount)?;
+    for section in &bmg.sections {
+        match section {
+            Section::INF1(inf1) => writeln!(result, "  INF1: {} messages, entry size {}", inf1.entries.len(), inf1.entrysize)?,
+            Section::DAT1(dat1) => writeln!(result, "  DAT1: {} bytes", dat1.data.len())?,
+            Section::FLW1(flw1) => writeln!(result, "  FLW1: {} nodes, {} branches", flw1.entries.len(), flw1.branch_nodes.len())?,
+            Section::FLI1(fli1) => writeln!(result, "  FLI1: {} entries", fli1.entrynum)?,
+            Section::MID1(mid1) => writeln!(result, "  MID1: {} ids", mid1.ids.len())?,
+            Section::Unknown { data, .. } => writeln!(result, "  {}: {} bytes", section.name(), data.len())?
+        }
+    }
+    write_output(None, result.as_bytes())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn validate(args: &Args) -> CommandResult {
+    args.check_options(&["profile"])?;
+    let (bmg, data) = read_bmg(args, args.input()?)?;
+    let mut problems = vec![];
+    if bmg.to_bytes()? != data {
+        problems.push(String::from("rewriting the file does not reproduce it byte for byte"));
+    }
+    let text = project::export(&bmg)?;
+    if project::import_with(&text, Some(bmg.profile.clone()))?.to_bytes()? != data {
+        problems.push(String::from("the text project does not rebuild the file byte for byte"));
+    }
+    if let Some(flw1) = bmg.get_flw1() {
+        let messages = bmg.get_inf1().map_or(0, |inf1| inf1.entries.len());
+        problems.extend(flw1.validate(messages, bmg.get_fli1()).iter().map(|problem| format!("flow: {problem}")));
+    }
+    if problems.is_empty() {
+        write_output(None, b"ok\n")?;
+        Ok(ExitCode::SUCCESS)
+    } else {
+        write_output(None, (problems.join("\n") + "\n").as_bytes())?;
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+fn flow(args: &Args) -> CommandResult {
+    args.check_options(&["output", "profile"])?;
+    let (subcommand, input) = match &args.positional[..] {
+        [subcommand, input] => (subcommand.as_str(), Some(input.as_str())),
+        [subcommand] if matches!(subcommand.as_str(), "list" | "graph") => (subcommand.as_str(), None),
+        [subcommand, _, _] if subcommand == "apply" => return flow_apply(args),
+        _ => ("list", args.input()?)
+    };
+    let (bmg, _) = read_bmg(args, input)?;
+    let inf1 = bmg.get_inf1().ok_or(BmgError::MissingSection(SectionMagic::INF1))?;
+    let flw1 = bmg.get_flw1().ok_or(BmgError::MissingSection(SectionMagic::FLW1))?;
+    let result = match subcommand {
+        "list" => flw1.to_listing(&flw1.entry_points(bmg.get_fli1(), inf1.entries.len())?, bmg.get_fli1(), &bmg.profile)?,
+        "graph" => {
+            let dat1 = bmg.get_dat1().ok_or(BmgError::MissingSection(SectionMagic::DAT1))?;
+            flw1.to_dot(inf1, dat1, &bmg.profile)?
+        },
+        other => return Err(Usage(format!("unknown flow command `{other}`")).into())
+    };
+    write_output(args.output(), result.as_bytes())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn flow_apply(args: &Args) -> CommandResult {
+    let (mut bmg, _) = read_bmg(args, Some(&args.positional[1]))?;
+    let listing = String::from_utf8(read_input(Some(&args.positional[2]))?)?;
+    let (mut flw1, flows) = FLW1::from_listing(&listing, &bmg.profile)?;
+    match bmg.get_flw1_mut() {
+        Some(target) => {
+            flw1.padding = target.padding;
+            *target = flw1;
+        },
+        None => bmg.sections.push(Section::FLW1(flw1))
+    }
+    // FLI1 points into FLW1, so it follows the [flowid:..] tags of the listing.
+    match bmg.get_fli1_mut() {
+        Some(fli1) => {
+            fli1.entrynum = flows.len() as u16;
+            fli1.entries = flows;
+        },
+        None if !flows.is_empty() => {
+            bmg.sections.retain(|section| section.magic() != SectionMagic::FLI1 as u32);
+            bmg.sections.push(Section::FLI1(FLI1 { entrynum: flows.len() as u16, entrylength: 8, entries: flows, ..Default::default() }));
+        },
+        None => {}
+    }
+    write_output(args.output(), &bmg.to_bytes()?)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn simulate(args: &Args) -> CommandResult {
+    args.check_options(&["message", "node", "profile"])?;
+    let path = match args.input()? {
+        None | Some("-") => return Err(Usage(String::from("simulate reads answers from stdin, give the BMG as a path")).into()),
+        Some(path) => path
+    };
+    let (bmg, _) = read_bmg(args, Some(path))?;
+    let inf1 = bmg.get_inf1().ok_or(BmgError::MissingSection(SectionMagic::INF1))?;
+    let dat1 = bmg.get_dat1().ok_or(BmgError::MissingSection(SectionMagic::DAT1))?;
+    let flw1 = bmg.get_flw1().ok_or(BmgError::MissingSection(SectionMagic::FLW1))?;
+    let number = |name: &str| args.options.get(name)
+        .map(|value| value.parse::<u16>().map_err(|_| Usage(format!("bad number `{value}` for `--{name}`"))))
+        .transpose();
+    let mut walker = match (number("message")?, number("node")?) {
+        (Some(message), None) => match FlowWalker::from_message(flw1, bmg.get_fli1(), message)? {
+            Some(walker) => walker,
+            None => {
+                eprintln!("error: no flow starts at message {message}");
+                return Ok(ExitCode::FAILURE);
             }
+        },
+        (None, Some(node)) => FlowWalker::new(flw1, node)?,
+        _ => return Err(Usage(String::from("simulate needs exactly one of `--message` and `--node`")).into())
+    };
+    let mut out = std::io::stdout().lock();
+    let mut answers = std::io::stdin().lines();
+    // Nodes seen since the last condition, a repeat means the flow loops by itself.
+    let mut seen = vec![];
+    while let Some(entry) = walker.entry() {
+        let node = walker.node().unwrap_or_default();
+        if seen.contains(&node) {
+            writeln!(out, "flow loops back to node {node} without asking anything")?;
+            return Ok(ExitCode::FAILURE);
+        }
+        seen.push(node);
+        let outcome = match entry {
+            FLW1Entry::Text(text) => {
+                writeln!(out, "{}", message_line(&bmg, inf1, dat1, text.textid)?)?;
+                true
+            },
+            FLW1Entry::Condition(con) => {
+                seen.clear();
+                let (prompt, yes, no) = match bmg.profile.choices(con.conditiontype) {
+                    Some(choices) => {
+                        let choice = bmg.profile.value_name(Some(choices), con.arg as u32);
+                        writeln!(out, "choice {choice}:")?;
+                        writeln!(out, "  1: {}", answer_line(&bmg, inf1, dat1, &walker, true)?)?;
+                        writeln!(out, "  2: {}", answer_line(&bmg, inf1, dat1, &walker, false)?)?;
+                        (String::from("answer? [1/2] "), "1", "2")
+                    },
+                    None => {
+                        let name = bmg.profile.condition_name(con.conditiontype);
+                        (format!("condition {name} (arg {})? [y/n] ", con.arg), "y", "n")
+                    }
+                };
+                loop {
+                    write!(out, "{prompt}")?;
+                    out.flush()?;
+                    let Some(answer) = answers.next().transpose()? else {
+                        writeln!(out)?;
+                        return Ok(ExitCode::SUCCESS);
+                    };
+                    match answer.trim() {
+                        "q" | "quit" => return Ok(ExitCode::SUCCESS),
+                        answer if answer == yes || answer == "yes" => break true,
+                        answer if answer == no || answer == "no" => break false,
+                        _ => {}
+                    }
+                }
+            },
+            FLW1Entry::Event(eve) => {
+                writeln!(out, "event {} (arg {})", bmg.profile.event_name(eve.event_type), eve.arg)?;
+                true
+            }
+        };
+        walker.advance(outcome)?;
+    }
+    writeln!(out, "end of flow")?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn message_line(bmg: &BMG, inf1: &INF1, dat1: &DAT1, textid: u16) -> Result<String, BmgError> {
+    let message = match inf1.entries.get(textid as usize) {
+        Some(_) => bmg.profile.get_string(inf1, textid as usize, dat1)?,
+        None => String::from("(no such message)")
+    };
+    Ok(format!("[message:{textid}] {message}"))
+}
+
+/// What an answer leads to: the first message after it, past any events.
+fn answer_line(bmg: &BMG, inf1: &INF1, dat1: &DAT1, walker: &FlowWalker, outcome: bool) -> Result<String, BmgError> {
+    let mut walker = walker.clone();
+    let mut entry = walker.advance(outcome)?;
+    let nodes = bmg.get_flw1().map_or(0, |flw1| flw1.entries.len());
+    for _ in 0..nodes {
+        match entry {
+            Some(FLW1Entry::Event(_)) => entry = walker.advance(true)?,
+            _ => break
         }
     }
-    for i in 0..converted.len() {
-        if !converted[i] {
-            let txt = flw1.findnode(i as u16, &mut converted)?;
-            if !txt.is_empty() {
-                println!("[beginflow]\n{txt}[endflow]")
+    match entry {
+        Some(FLW1Entry::Text(text)) => message_line(bmg, inf1, dat1, text.textid),
+        Some(_) => Ok(format!("node {}", walker.node().unwrap_or_default())),
+        None => Ok(String::from("end of flow"))
+    }
+}
+
+fn test_flows(args: &Args) -> CommandResult {
+    args.check_options(&["profile"])?;
+    let [path, scenarios] = &args.positional[..] else {
+        return Err(Usage(String::from("test-flows needs a BMG and a scenario file")).into());
+    };
+    let (bmg, _) = read_bmg(args, Some(path))?;
+    let scenarios = scenario::parse(&String::from_utf8(read_input(Some(scenarios))?)?)?;
+    let flw1 = bmg.get_flw1().ok_or(BmgError::MissingSection(SectionMagic::FLW1))?;
+    let mut result = String::new();
+    let mut failed = 0;
+    for scenario in &scenarios {
+        let failures = scenario.run(flw1, bmg.get_fli1(), &bmg.profile);
+        if failures.is_empty() {
+            writeln!(result, "ok {}", scenario.name)?;
+        } else {
+            failed += 1;
+            writeln!(result, "FAIL {} (line {})", scenario.name, scenario.line)?;
+            for failure in failures {
+                writeln!(result, "  {failure}")?;
             }
         }
     }
+    writeln!(result, "{} passed, {failed} failed", scenarios.len() - failed)?;
+    write_output(None, result.as_bytes())?;
+    Ok(if failed == 0 { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
+
+fn convert(args: &Args) -> CommandResult {
+    args.check_options(&["output", "endian", "encoding", "profile"])?;
+    let (mut bmg, _) = read_bmg(args, args.input()?)?;
+    if let Some(endian) = args.options.get("endian") {
+        let endian = match endian.as_str() {
+            "big" => Endian::Big,
+            "little" => Endian::Little,
+            "swap" if bmg.header.endian() == Endian::Big => Endian::Little,
+            "swap" => Endian::Big,
+            other => return Err(Usage(format!("unknown byte order `{other}`")).into())
+        };
+        bmg.set_endian(endian)?;
+    }
+    set_encoding(args, &mut bmg)?;
+    write_output(args.output(), &bmg.to_bytes()?)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn profile(args: &Args) -> CommandResult {
+    args.check_options(&["output"])?;
+    let name = args.input()?.ok_or_else(|| Usage(String::from("missing game")))?;
+    let profile = Profile::builtin(name).ok_or_else(|| Usage(format!("unknown profile `{name}`")))?;
+    write_output(args.output(), profile.to_string().as_bytes())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn set_encoding(args: &Args, bmg: &mut BMG) -> Result<(), Failure> {
+    if let Some(name) = args.options.get("encoding") {
+        let encoding = Encoding::from_name(name).ok_or_else(|| Usage(format!("unknown encoding `{name}`")))?;
+        bmg.set_encoding(encoding)?;
+    }
     Ok(())
 }
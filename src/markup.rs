@@ -0,0 +1,240 @@
+use crate::encoding::Encoding;
+use binrw::Endian;
+use std::fmt;
+
+pub const ESCAPE: u16 = 0x1A;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkupError {
+    pub position: usize,
+    pub reason: String
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bad tag at {}: {}", self.position, self.reason)
+    }
+}
+
+impl std::error::Error for MarkupError {}
+
+/// A piece of a message. The escapes the Galaxy games use have variants of
+/// their own holding plain numbers, a [`crate::profile::Profile`] lays them
+/// out and names the numbers, and only decodes them when it has an escape of
+/// that name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextSegment {
+    Text(String),
+    Color(u8),
+    Icon(u16),
+    Wait(u8),
+    NewLine,
+    Center,
+    FontSize(u16),
+    PlayerName(u8),
+    ValInt { value: u16, arg2: u32, arg3: u32 },
+    ValStr { value: u16, arg2: u32, arg3: u32 },
+    Sound(String),
+    /// An escape kept as it is in the file: `bytes` holds the value and the
+    /// arguments in the file's byte order.
+    Raw { ty: u8, bytes: Vec<u8> },
+    /// Any other escape named by a profile.
+    Tag { name: String, args: Vec<String> }
+}
+
+impl TextSegment {
+    /// The typed segment for the tag `name` with the numbers and the text of
+    /// its fields, `None` if there's no variant for it.
+    pub(crate) fn from_values(name: &str, numbers: &[u32], text: Option<&str>) -> Option<Self> {
+        let segment = match (name, numbers, text) {
+            ("color", &[color], None) => Self::Color(color.try_into().ok()?),
+            ("icon", &[icon], None) => Self::Icon(icon.try_into().ok()?),
+            ("waittime", &[time], None) => Self::Wait(time.try_into().ok()?),
+            ("newline", [], None) => Self::NewLine,
+            ("center", [], None) => Self::Center,
+            ("fontsize", &[size], None) => Self::FontSize(size.try_into().ok()?),
+            ("playername", &[name], None) => Self::PlayerName(name.try_into().ok()?),
+            ("valint", &[value, arg2, arg3], None) => Self::ValInt { value: value.try_into().ok()?, arg2, arg3 },
+            ("valstr", &[value, arg2, arg3], None) => Self::ValStr { value: value.try_into().ok()?, arg2, arg3 },
+            ("sound", [], Some(name)) => Self::Sound(name.to_string()),
+            _ => return None
+        };
+        Some(segment)
+    }
+    /// The tag name, numbers and text of a typed segment.
+    pub(crate) fn values(&self) -> Option<(&'static str, Vec<u32>, Option<&str>)> {
+        Some(match self {
+            Self::Color(color) => ("color", vec![*color as u32], None),
+            Self::Icon(icon) => ("icon", vec![*icon as u32], None),
+            Self::Wait(time) => ("waittime", vec![*time as u32], None),
+            Self::NewLine => ("newline", vec![], None),
+            Self::Center => ("center", vec![], None),
+            Self::FontSize(size) => ("fontsize", vec![*size as u32], None),
+            Self::PlayerName(name) => ("playername", vec![*name as u32], None),
+            Self::ValInt { value, arg2, arg3 } => ("valint", vec![*value as u32, *arg2, *arg3], None),
+            Self::ValStr { value, arg2, arg3 } => ("valstr", vec![*value as u32, *arg2, *arg3], None),
+            Self::Sound(name) => ("sound", vec![], Some(name.as_str())),
+            Self::Text(_) | Self::Raw { .. } | Self::Tag { .. } => return None
+        })
+    }
+}
+
+/// Typed segments are written with plain numbers here, [`crate::profile::Profile::render`]
+/// names them.
+impl fmt::Display for TextSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Text(text) => write!(f, "{text}"),
+            Self::Color(color) => write!(f, "[color:{color}]"),
+            Self::Icon(icon) => write!(f, "[icon:{icon}]"),
+            Self::Wait(time) => write!(f, "[waittime:{time}]"),
+            Self::NewLine => write!(f, "[newline]"),
+            Self::Center => write!(f, "[center]"),
+            Self::FontSize(size) => write!(f, "[fontsize:{size}]"),
+            Self::PlayerName(name) => write!(f, "[playername:{name}]"),
+            Self::ValInt { value, arg2, arg3 } => write!(f, "[valint:{value},{arg2},{arg3}]"),
+            Self::ValStr { value, arg2, arg3 } => write!(f, "[valstr:{value},{arg2},{arg3}]"),
+            Self::Sound(name) => write!(f, "[sound:{name}]"),
+            Self::Raw { ty, bytes } => {
+                write!(f, "[raw:{ty:02x}")?;
+                for byte in bytes {
+                    write!(f, " {byte:02x}")?;
+                }
+                write!(f, "]")
+            },
+            Self::Tag { name, args } if args.is_empty() => write!(f, "[{name}]"),
+            Self::Tag { name, args } => write!(f, "[{name}:{}]", args.join(","))
+        }
+    }
+}
+
+pub fn render(segments: &[TextSegment]) -> String {
+    segments.iter().map(TextSegment::to_string).collect()
+}
+
+/// Splits text into segments, `parse_tag` turns the inside of a `[..]` into a
+/// segment or leaves it as text by returning `None`.
+pub(crate) fn parse_with<F>(text: &str, parse_tag: F) -> Result<Vec<TextSegment>, MarkupError>
+where F: Fn(&str, usize) -> Result<Option<TextSegment>, MarkupError> {
+    let mut result = vec![];
+    let mut rest = text;
+    while let Some(start) = rest.find('[') {
+        push_text(&mut result, &rest[..start]);
+        let position = text.len() - rest.len() + start;
+        let tag = &rest[(start + 1)..];
+        match tag.find(']') {
+            Some(end) if let Some(segment) = parse_tag(&tag[..end], position)? => {
+                result.push(segment);
+                rest = &tag[(end + 1)..];
+            },
+            _ => {
+                push_text(&mut result, "[");
+                rest = tag;
+            }
+        }
+    }
+    push_text(&mut result, rest);
+    Ok(result)
+}
+
+/// Errors from here carry the index of the offending segment as their position.
+pub fn encode(segments: &[TextSegment], endian: Endian, encoding: Encoding) -> Result<Vec<u8>, MarkupError> {
+    let mut result = vec![];
+    for (position, segment) in segments.iter().enumerate() {
+        let too_long = || MarkupError { position, reason: format!("{segment} is too long for an escape") };
+        let unencodable = || MarkupError { position, reason: format!("`{segment}` can't be written in {encoding:?}") };
+        match segment {
+            TextSegment::Text(text) => result.extend(encoding.encode(text, endian).ok_or_else(unencodable)?),
+            TextSegment::Raw { ty, bytes } => {
+                if bytes.len() < 2 || bytes.len() > u8::MAX as usize - encoding.unit() - 2 {
+                    return Err(too_long());
+                }
+                encoding.push_unit(&mut result, ESCAPE, endian);
+                result.push((encoding.unit() + 2 + bytes.len()) as u8);
+                result.push(*ty);
+                result.extend_from_slice(bytes);
+            },
+            _ => return Err(MarkupError {
+                position,
+                reason: format!("{segment} needs a game profile to be encoded")
+            })
+        }
+    }
+    Ok(result)
+}
+
+fn push_text(result: &mut Vec<TextSegment>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match result.last_mut() {
+        Some(TextSegment::Text(last)) => last.push_str(text),
+        _ => result.push(TextSegment::Text(text.to_string()))
+    }
+}
+
+/// Reads `[raw:..]`, the one tag that needs no profile.
+pub(crate) fn parse_raw(tag: &str, position: usize) -> Result<Option<TextSegment>, MarkupError> {
+    let Some(arg) = tag.strip_prefix("raw:") else {
+        return Ok(None);
+    };
+    let error = |reason: String| MarkupError { position, reason };
+    let bytes = arg.split_whitespace()
+        .map(|byte| u8::from_str_radix(byte, 16))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| error(format!("expected hex bytes in [{tag}]")))?;
+    match bytes.split_first() {
+        Some((&ty, bytes)) if (2..=(u8::MAX as usize - 4)).contains(&bytes.len()) => {
+            Ok(Some(TextSegment::Raw { ty, bytes: bytes.to_vec() }))
+        },
+        _ => Err(error(format!("expected a type and 2 to 251 bytes in [{tag}]")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw_round_trip() {
+        let segments = parse_with("a[raw:01 00 05 00 02]b[c]", parse_raw).unwrap();
+        assert_eq!(segments, [
+            TextSegment::Text(String::from("a")),
+            TextSegment::Raw { ty: 1, bytes: vec![0, 5, 0, 2] },
+            TextSegment::Text(String::from("b[c]"))
+        ]);
+        assert_eq!(render(&segments), "a[raw:01 00 05 00 02]b[c]");
+        assert_eq!(encode(&segments, Endian::Big, Encoding::Utf16).unwrap(),
+            [0, b'a', 0, 0x1a, 8, 1, 0, 5, 0, 2, 0, b'b', 0, b'[', 0, b'c', 0, b']']);
+        assert_eq!(encode(&segments, Endian::Little, Encoding::Cp1252).unwrap(),
+            [b'a', 0x1a, 7, 1, 0, 5, 0, 2, b'b', b'[', b'c', b']']);
+    }
+
+    #[test]
+    fn typed_segments_convert_both_ways() {
+        for segment in [TextSegment::Color(3), TextSegment::NewLine, TextSegment::ValStr { value: 1, arg2: 2, arg3: 3 },
+            TextSegment::Sound(String::from("SE"))] {
+            let (name, numbers, text) = segment.values().unwrap();
+            assert_eq!(TextSegment::from_values(name, &numbers, text), Some(segment));
+        }
+        assert_eq!(TextSegment::from_values("color", &[256], None), None);
+        assert_eq!(TextSegment::from_values("newline", &[1], None), None);
+        assert_eq!(TextSegment::from_values("furigana", &[1], Some("a")), None);
+        assert_eq!(render(&[TextSegment::Wait(30), TextSegment::ValInt { value: 3, arg2: 1, arg3: 2 }]), "[waittime:30][valint:3,1,2]");
+    }
+
+    #[test]
+    fn bad_raw() {
+        assert_eq!(parse_with("ab[raw:01 00]", parse_raw).unwrap_err().position, 2);
+        assert!(parse_with("[raw:zz 00 00]", parse_raw).is_err());
+        assert!(parse_with("[raw]", parse_raw).is_ok());
+    }
+
+    #[test]
+    fn encode_needs_a_profile_for_tags() {
+        let tag = TextSegment::Tag { name: String::from("newline"), args: vec![] };
+        assert_eq!(encode(&[TextSegment::Text(String::from("a")), tag], Endian::Big, Encoding::Utf16).unwrap_err().position, 1);
+        assert!(encode(&[TextSegment::NewLine], Endian::Big, Encoding::Utf16).is_err());
+        assert!(encode(&[TextSegment::Text(String::from("♪"))], Endian::Big, Encoding::Cp1252).is_err());
+    }
+}
@@ -0,0 +1,850 @@
+//! Game profiles.
+//!
+//! Many Nintendo games share the BMG container, but each lays out the INF1
+//! attributes its own way and has its own set of escapes. A [`Profile`]
+//! describes both, so any game it knows about can be exported as named
+//! attributes and `[tag:..]` markup and built again byte for byte.
+//!
+//! Escapes are only decoded with a profile's names when they encode back to
+//! the same bytes, anything else stays `[raw:..]`.
+//!
+//! Profiles are read from definitions made of `[key:value]` lines, the
+//! built-in ones are in the `profiles` directory:
+//!
+//! ```text
+//! # Super Mario Galaxy.
+//! [profile:smg1][entrysize:12][encoding:utf16]
+//! [attribute:type][offset:4][size:1][table:messagetypes]
+//! [attribute:cameraid][offset:0][size:2]
+//! [escape:newline][type:1][fields:u16=1]
+//! [escape:sound][type:2][fields:u16=0,text]
+//! [escape:color][type:255][fields:u16=0,u8:colors,u8=0]
+//! [table:messagetypes][value:0][name:Talk]
+//! [condition:MultipleChoice][type:0][choice:choices]
+//! [condition:PlayerLuigi][type:10]
+//! [event:Emotion][type:4]
+//! [table:colors][value:1][name:red]
+//! [table:choices][value:0][name:PenguinRace]
+//! ```
+//!
+//! The `[profile:..]` line comes first, `[messageids]` on it says the game's
+//! files have a MID1 section. Attribute offsets count from the end of the text
+//! address. Escape fields follow the type byte and are `u8`, `u16` or `u32`,
+//! with `=value` for fixed values or `:table` for names, and `text` for the
+//! rest of the escape. Conditions and events name the FLW1 node types, a
+//! condition with `[choice:table]` asks the player to pick an answer and its
+//! argument is named from that table. Blank lines and lines starting with `#`
+//! are ignored.
+
+use crate::bmg::*;
+use crate::encoding::Encoding;
+use crate::error::BmgError;
+use crate::markup::{self, MarkupError, TextSegment};
+use crate::project::{ProjectError, Tags};
+use binrw::Endian;
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    /// Size of an INF1 entry, the text address included.
+    pub entrysize: u16,
+    /// Whether the game's files come with a MID1 section.
+    pub message_ids: bool,
+    pub encoding: Encoding,
+    /// The attributes after the text address, in the order they're exported.
+    pub attributes: Vec<Attribute>,
+    pub escapes: Vec<EscapeDef>,
+    pub conditions: Vec<ConditionDef>,
+    pub events: Vec<EventDef>,
+    /// Names for numbers, used by attributes and escape fields.
+    pub tables: HashMap<String, Vec<(u32, String)>>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribute {
+    pub name: String,
+    /// Byte offset from the end of the text address.
+    pub offset: usize,
+    /// 1, 2 or 4 bytes.
+    pub size: usize,
+    pub table: Option<String>
+}
+
+/// An escape that is written as `[tag]` or `[tag:arg,..]`, one argument for
+/// each field that isn't fixed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EscapeDef {
+    pub tag: String,
+    pub ty: u8,
+    /// Everything after the type byte, starting with the 2 byte value.
+    pub fields: Vec<Field>
+}
+
+/// A condition type of flow nodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConditionDef {
+    pub name: String,
+    pub ty: u16,
+    /// The table naming the argument of a condition that asks the player to
+    /// pick between two answers.
+    pub choice: Option<String>
+}
+
+/// An event type of flow nodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventDef {
+    pub name: String,
+    pub ty: u8
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Field {
+    Fixed { size: usize, value: u32 },
+    Number { size: usize, table: Option<String> },
+    /// Text in the rest of the escape.
+    Text
+}
+
+impl EscapeDef {
+    fn arity(&self) -> usize {
+        self.fields.iter().filter(|field| !matches!(field, Field::Fixed { .. })).count()
+    }
+    fn fits(&self, len: usize, encoding: Encoding) -> bool {
+        let fixed = self.fields.iter().map(|field| match field {
+            Field::Fixed { size, .. } | Field::Number { size, .. } => *size,
+            Field::Text => 0
+        }).sum::<usize>();
+        if self.fields.contains(&Field::Text) {
+            fixed <= len && (len - fixed).is_multiple_of(encoding.unit())
+        } else {
+            fixed == len
+        }
+    }
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self::builtin("smg1").expect("smg1 is built in")
+    }
+}
+
+const DEFINITIONS: [(&str, &str); 6] = [
+    ("smg1", include_str!("../profiles/smg1.profile")),
+    ("mkw", include_str!("../profiles/mkw.profile")),
+    ("ww", include_str!("../profiles/ww.profile")),
+    ("tp", include_str!("../profiles/tp.profile")),
+    ("sms", include_str!("../profiles/sms.profile")),
+    ("lm", include_str!("../profiles/lm.profile"))
+];
+
+impl Profile {
+    pub const BUILTIN: [&str; 6] = ["smg1", "mkw", "ww", "tp", "sms", "lm"];
+    pub fn builtin(name: &str) -> Option<Self> {
+        let (_, definition) = DEFINITIONS.iter().find(|(builtin, _)| *builtin == name)?;
+        Some(Self::from_definition(definition).expect("built-in profiles are valid"))
+    }
+    /// Picks the built-in profile that fits a file best. The INF1 entry size
+    /// has to match, then a MID1 section, how much of the text decodes to the
+    /// profile's tags and characters, and the encoding byte decide, in that
+    /// order. The encoding byte is only a hint: text is decoded with it when
+    /// it's set and with the profile's own encoding when it's 0, so a file
+    /// converted to another encoding is still recognised by its layout.
+    pub fn detect(header: &FileHeader, sections: &[Section]) -> Self {
+        let inf1 = sections.iter().find_map(|section| match section {
+            Section::INF1(inf1) => Some(inf1),
+            _ => None
+        });
+        let dat1 = sections.iter().find_map(|section| match section {
+            Section::DAT1(dat1) => Some(dat1),
+            _ => None
+        });
+        let message_ids = sections.iter().any(|section| section.magic() == SectionMagic::MID1 as u32);
+        let byte = header.encoding();
+        // Reversed so ties go to the profile listed first.
+        Self::BUILTIN.iter().rev().filter_map(|name| Self::builtin(name))
+            .filter(|profile| inf1.is_none_or(|inf1| inf1.entrysize == profile.entrysize))
+            .max_by_key(|profile| {
+                let score = inf1.zip(dat1).map(|(inf1, dat1)| {
+                    let encoding = byte.unwrap_or(profile.encoding);
+                    profile.score(inf1, &DAT1 { encoding, ..dat1.clone() })
+                });
+                (profile.message_ids == message_ids, score, byte == Some(profile.encoding))
+            })
+            .unwrap_or_default()
+    }
+    /// How many tags and characters the messages decode to. Messages that
+    /// don't decode count for nothing.
+    fn score(&self, inf1: &INF1, dat1: &DAT1) -> (usize, usize) {
+        let mut score = (0, 0);
+        for index in 0..inf1.entries.len() {
+            for segment in self.get_segments(inf1, index, dat1).unwrap_or_default() {
+                match segment {
+                    TextSegment::Text(text) => score.1 += text.chars()
+                        .filter(|&c| c != char::REPLACEMENT_CHARACTER && (c == '\n' || !c.is_control())).count(),
+                    TextSegment::Raw { .. } => (),
+                    _ => score.0 += 1
+                }
+            }
+        }
+        score
+    }
+    /// Reads a profile definition, see the module documentation for the format.
+    pub fn from_definition(text: &str) -> Result<Self, ProjectError> {
+        let mut profile: Option<Self> = None;
+        let mut references = vec![];
+        let mut last = 0;
+        for (number, line) in text.split('\n').enumerate().map(|(i, line)| (i + 1, line.trim_end())) {
+            last = number;
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |reason: String| ProjectError { line: number, reason };
+            let tags = Tags::parse(line).ok_or_else(|| error(format!("expected tags, found `{line}`")))?;
+            if tags.first() == "profile" {
+                if profile.is_some() {
+                    return Err(error(String::from("there can only be one [profile:..] line")));
+                }
+                let encoding = tags.get("encoding").map_err(error)?;
+                let entrysize: u16 = tags.number("entrysize").map_err(error)?;
+                if entrysize < 4 {
+                    return Err(error(format!("entry size {entrysize} can't hold a text address")));
+                }
+                profile = Some(Self {
+                    name: tags.get("profile").map_err(error)?.to_string(),
+                    entrysize,
+                    message_ids: tags.has("messageids"),
+                    encoding: Encoding::from_name(encoding).ok_or_else(|| error(format!("unknown encoding `{encoding}`")))?,
+                    attributes: vec![],
+                    escapes: vec![],
+                    conditions: vec![],
+                    events: vec![],
+                    tables: HashMap::new()
+                });
+                continue;
+            }
+            let profile = profile.as_mut().ok_or_else(|| error(String::from("expected a [profile:..] line first")))?;
+            match tags.first() {
+                "attribute" => {
+                    let attribute = Attribute {
+                        name: tags.get("attribute").map_err(error)?.to_string(),
+                        offset: tags.number("offset").map_err(error)?,
+                        size: tags.number("size").map_err(error)?,
+                        table: tags.has("table").then(|| tags.get("table").map(str::to_string)).transpose().map_err(error)?
+                    };
+                    if ![1, 2, 4].contains(&attribute.size) {
+                        return Err(error(format!("attribute size must be 1, 2 or 4, not {}", attribute.size)));
+                    }
+                    if attribute.offset + attribute.size + 4 > profile.entrysize as usize {
+                        return Err(error(format!("{} doesn't fit an INF1 entry of {} bytes", attribute.name, profile.entrysize)));
+                    }
+                    references.extend(attribute.table.clone().map(|table| (number, table)));
+                    profile.attributes.push(attribute);
+                },
+                "escape" => {
+                    let ty = tags.number("type").map_err(error)?;
+                    let fields = tags.get("fields").map_err(error)?;
+                    let fields = if fields.is_empty() { vec![] } else {
+                        fields.split(',').map(parse_field).collect::<Result<Vec<_>, _>>().map_err(error)?
+                    };
+                    if fields.iter().rev().skip(1).any(|field| *field == Field::Text) {
+                        return Err(error(String::from("text can only be the last field")));
+                    }
+                    references.extend(fields.iter().filter_map(|field| match field {
+                        Field::Number { table: Some(table), .. } => Some((number, table.clone())),
+                        _ => None
+                    }));
+                    profile.escapes.push(EscapeDef { tag: tags.get("escape").map_err(error)?.to_string(), ty, fields });
+                },
+                "condition" => {
+                    let condition = ConditionDef {
+                        name: tags.get("condition").map_err(error)?.to_string(),
+                        ty: tags.number("type").map_err(error)?,
+                        choice: tags.has("choice").then(|| tags.get("choice").map(str::to_string)).transpose().map_err(error)?
+                    };
+                    check_name(&condition.name).map_err(error)?;
+                    if profile.conditions.iter().any(|c| c.name == condition.name || c.ty == condition.ty) {
+                        return Err(error(format!("condition {} or type {} is defined twice", condition.name, condition.ty)));
+                    }
+                    references.extend(condition.choice.clone().map(|table| (number, table)));
+                    profile.conditions.push(condition);
+                },
+                "event" => {
+                    let event = EventDef {
+                        name: tags.get("event").map_err(error)?.to_string(),
+                        ty: tags.number("type").map_err(error)?
+                    };
+                    check_name(&event.name).map_err(error)?;
+                    if profile.events.iter().any(|e| e.name == event.name || e.ty == event.ty) {
+                        return Err(error(format!("event {} or type {} is defined twice", event.name, event.ty)));
+                    }
+                    profile.events.push(event);
+                },
+                "table" => {
+                    let value = tags.number("value").map_err(error)?;
+                    let name = tags.get("name").map_err(error)?;
+                    check_name(name).map_err(error)?;
+                    let names = profile.tables.entry(tags.get("table").map_err(error)?.to_string()).or_default();
+                    if names.iter().any(|(n, _)| *n == value) {
+                        return Err(error(format!("{value} already has a name")));
+                    }
+                    names.push((value, name.to_string()));
+                },
+                other => return Err(error(format!("unknown line [{other}]")))
+            }
+        }
+        let profile = profile.ok_or(ProjectError { line: last, reason: String::from("missing [profile:..] line") })?;
+        match references.into_iter().find(|(_, table)| !profile.tables.contains_key(table)) {
+            Some((line, table)) => Err(ProjectError { line, reason: format!("there is no table `{table}`") }),
+            None => Ok(profile)
+        }
+    }
+
+    /// The name of a number in a table, or the number itself.
+    pub fn value_name(&self, table: Option<&str>, value: u32) -> String {
+        table.and_then(|table| self.tables.get(table))
+            .and_then(|names| names.iter().find(|(n, _)| *n == value))
+            .map_or_else(|| value.to_string(), |(_, name)| name.clone())
+    }
+    /// Looks up a name in a table, numbers are taken as they are.
+    pub fn value(&self, table: Option<&str>, name: &str) -> Option<u32> {
+        name.parse().ok().or_else(|| table.and_then(|table| self.tables.get(table))?
+            .iter().find(|(_, n)| n.eq_ignore_ascii_case(name)).map(|(value, _)| *value))
+    }
+
+    /// The name of a condition type, or the number itself.
+    pub fn condition_name(&self, ty: u16) -> String {
+        self.conditions.iter().find(|c| c.ty == ty).map_or_else(|| ty.to_string(), |c| c.name.clone())
+    }
+    /// Looks up a condition type by name, numbers are taken as they are.
+    pub fn condition_type(&self, name: &str) -> Option<u16> {
+        name.parse().ok().or_else(|| self.conditions.iter().find(|c| c.name == name).map(|c| c.ty))
+    }
+    /// The choice table of a condition type that asks the player, `None` for
+    /// any other condition.
+    pub fn choices(&self, ty: u16) -> Option<&str> {
+        self.conditions.iter().find(|c| c.ty == ty)?.choice.as_deref()
+    }
+    /// The name of an event type, or the number itself.
+    pub fn event_name(&self, ty: u8) -> String {
+        self.events.iter().find(|e| e.ty == ty).map_or_else(|| ty.to_string(), |e| e.name.clone())
+    }
+    /// Looks up an event type by name, numbers are taken as they are.
+    pub fn event_type(&self, name: &str) -> Option<u8> {
+        name.parse().ok().or_else(|| self.events.iter().find(|e| e.name == name).map(|e| e.ty))
+    }
+
+    /// Names and values of the attributes of an entry, `None` if the entry
+    /// doesn't have this profile's size.
+    pub fn read_attributes(&self, attributes: &[u8], endian: Endian) -> Option<Vec<(&str, String)>> {
+        if attributes.len() + 4 != self.entrysize as usize {
+            return None;
+        }
+        self.attributes.iter().map(|attribute| {
+            let bytes = attributes.get(attribute.offset..(attribute.offset + attribute.size))?;
+            Some((attribute.name.as_str(), self.value_name(attribute.table.as_deref(), read_uint(bytes, endian))))
+        }).collect()
+    }
+    /// Builds the attributes of an entry, `get` gives the value of an
+    /// attribute by name.
+    pub fn write_attributes<'a, F>(&self, get: F, endian: Endian) -> Result<Vec<u8>, String>
+    where F: Fn(&str) -> Result<&'a str, String> {
+        let mut result = vec![0; (self.entrysize as usize).saturating_sub(4)];
+        for attribute in &self.attributes {
+            let name = get(&attribute.name)?;
+            let value = self.value(attribute.table.as_deref(), name)
+                .ok_or_else(|| format!("unknown {} `{name}`", attribute.name))?;
+            let bytes = uint_bytes(value, attribute.size, endian)
+                .ok_or_else(|| format!("{} `{name}` is out of range", attribute.name))?;
+            result.get_mut(attribute.offset..(attribute.offset + attribute.size))
+                .ok_or_else(|| format!("{} doesn't fit an INF1 entry of {} bytes", attribute.name, self.entrysize))?
+                .copy_from_slice(&bytes);
+        }
+        Ok(result)
+    }
+    /// Reverses the byte order of the multi-byte attributes of an entry.
+    pub fn swap_attributes(&self, attributes: &mut [u8]) {
+        if attributes.len() + 4 != self.entrysize as usize {
+            return;
+        }
+        for attribute in &self.attributes {
+            if let Some(bytes) = attributes.get_mut(attribute.offset..(attribute.offset + attribute.size)) {
+                bytes.reverse();
+            }
+        }
+    }
+    /// Reverses the byte order of an escape, `body` starts after the type byte.
+    pub fn swap_escape(&self, ty: u8, body: &mut [u8], encoding: Encoding) {
+        let Some(def) = self.escapes.iter().find(|def| def.ty == ty && def.fits(body.len(), encoding)) else {
+            if let Some(value) = body.get_mut(0..2) {
+                value.reverse();
+            }
+            return;
+        };
+        let mut i = 0;
+        for field in &def.fields {
+            match field {
+                Field::Fixed { size, .. } | Field::Number { size, .. } => {
+                    body[i..(i + size)].reverse();
+                    i += size;
+                },
+                Field::Text => body[i..].chunks_exact_mut(encoding.unit()).for_each(|unit| unit.reverse())
+            }
+        }
+    }
+
+    pub fn get_segments(&self, inf1: &INF1, index: usize, dat1: &DAT1) -> Result<Vec<TextSegment>, BmgError> {
+        let entry = inf1.entries.get(index)
+            .ok_or(BmgError::OutOfBounds { section: SectionMagic::INF1, offset: index, message: Some(index) })?;
+        dat1.decode(entry.textaddress as usize, |start| self.read_escape(dat1, start))
+            .map_err(|e| e.in_message(index))
+    }
+    #[inline]
+    pub fn get_string(&self, inf1: &INF1, index: usize, dat1: &DAT1) -> Result<String, BmgError> {
+        Ok(self.render(&self.get_segments(inf1, index, dat1)?))
+    }
+    /// Writes segments as markup, with the numbers of typed segments named
+    /// from the profile's tables.
+    pub fn render(&self, segments: &[TextSegment]) -> String {
+        segments.iter().map(|segment| match self.untyped(segment) {
+            Some(tag) => tag.to_string(),
+            None => segment.to_string()
+        }).collect()
+    }
+    /// Compiles `text` and points message `index` at it. The bytes are added
+    /// at the end of DAT1, the old text stays where it was.
+    pub fn set_string(&self, inf1: &mut INF1, index: usize, dat1: &mut DAT1, text: &str) -> Result<(), BmgError> {
+        let bytes = self.compile(text, dat1.endian, dat1.encoding)?;
+        let entry = inf1.entries.get_mut(index)
+            .ok_or(BmgError::OutOfBounds { section: SectionMagic::INF1, offset: index, message: Some(index) })?;
+        entry.textaddress = dat1.push_string(&bytes);
+        Ok(())
+    }
+    #[inline]
+    pub fn compile(&self, text: &str, endian: Endian, encoding: Encoding) -> Result<Vec<u8>, MarkupError> {
+        self.encode(&self.parse(text)?, endian, encoding)
+    }
+    pub fn parse(&self, text: &str) -> Result<Vec<TextSegment>, MarkupError> {
+        markup::parse_with(text, |tag, position| self.parse_tag(tag, position))
+    }
+    /// Errors from here carry the index of the offending segment as their position.
+    pub fn encode(&self, segments: &[TextSegment], endian: Endian, encoding: Encoding) -> Result<Vec<u8>, MarkupError> {
+        let mut result = vec![];
+        for (position, segment) in segments.iter().enumerate() {
+            let tag = self.untyped(segment);
+            match tag.as_ref().unwrap_or(segment) {
+                TextSegment::Tag { name, args } => result.extend(self.encode_tag(name, args, endian, encoding)
+                    .map_err(|reason| MarkupError { position, reason })?),
+                _ => result.extend(markup::encode(std::slice::from_ref(segment), endian, encoding)
+                    .map_err(|error| MarkupError { position, ..error })?)
+            }
+        }
+        Ok(result)
+    }
+
+    fn read_escape(&self, dat1: &DAT1, start: usize) -> Result<(TextSegment, usize), BmgError> {
+        let escape = dat1.get_escape(start)?;
+        let header = dat1.encoding.unit() + 2;
+        let (ty, body) = (escape[header - 1], &escape[header..]);
+        let segment = self.escapes.iter().filter(|def| def.ty == ty)
+            .filter_map(|def| self.read_fields(def, body, dat1.endian, dat1.encoding))
+            .find(|segment| self.encode(std::slice::from_ref(segment), dat1.endian, dat1.encoding)
+                .is_ok_and(|bytes| bytes == escape))
+            .unwrap_or_else(|| TextSegment::Raw { ty, bytes: body.to_vec() });
+        Ok((segment, start + escape.len()))
+    }
+    fn read_fields(&self, def: &EscapeDef, mut body: &[u8], endian: Endian, encoding: Encoding) -> Option<TextSegment> {
+        let mut args = vec![];
+        for field in &def.fields {
+            match field {
+                Field::Fixed { size, value } => {
+                    let (bytes, rest) = body.split_at_checked(*size)?;
+                    if read_uint(bytes, endian) != *value {
+                        return None;
+                    }
+                    body = rest;
+                },
+                Field::Number { size, table } => {
+                    let (bytes, rest) = body.split_at_checked(*size)?;
+                    args.push(self.value_name(table.as_deref(), read_uint(bytes, endian)));
+                    body = rest;
+                },
+                Field::Text => {
+                    if !body.len().is_multiple_of(encoding.unit()) {
+                        return None;
+                    }
+                    args.push(encoding.decode(body, endian));
+                    body = &[];
+                }
+            }
+        }
+        body.is_empty().then(|| self.typed(TextSegment::Tag { name: def.tag.clone(), args }))
+    }
+    /// Turns the tag of an escape [`TextSegment`] has a variant for into that
+    /// variant, anything else is returned as it is.
+    fn typed(&self, segment: TextSegment) -> TextSegment {
+        let TextSegment::Tag { name, args } = &segment else {
+            return segment;
+        };
+        let Some(def) = self.escapes.iter().find(|def| def.tag == *name && def.arity() == args.len()) else {
+            return segment;
+        };
+        let (mut numbers, mut text) = (vec![], None);
+        for (field, arg) in def.fields.iter().filter(|field| !matches!(field, Field::Fixed { .. })).zip(args) {
+            match field {
+                Field::Number { table, .. } => match self.value(table.as_deref(), arg) {
+                    Some(number) => numbers.push(number),
+                    None => return segment
+                },
+                _ => text = Some(arg.as_str())
+            }
+        }
+        TextSegment::from_values(name, &numbers, text).unwrap_or(segment)
+    }
+    /// The tag a typed segment is written as, its numbers named from the
+    /// tables of the escape with its name. `None` for other segments.
+    fn untyped(&self, segment: &TextSegment) -> Option<TextSegment> {
+        let (name, numbers, text) = segment.values()?;
+        let arity = numbers.len() + text.is_some() as usize;
+        let args = match self.escapes.iter().find(|def| def.tag == name && def.arity() == arity) {
+            Some(def) => {
+                let mut numbers = numbers.iter();
+                def.fields.iter().filter_map(|field| match field {
+                    Field::Fixed { .. } => None,
+                    Field::Number { table, .. } => Some(numbers.next()
+                        .map_or_else(String::new, |&number| self.value_name(table.as_deref(), number))),
+                    Field::Text => Some(text.unwrap_or_default().to_string())
+                }).collect()
+            },
+            None => numbers.iter().map(u32::to_string).chain(text.map(str::to_string)).collect()
+        };
+        Some(TextSegment::Tag { name: name.to_string(), args })
+    }
+    fn encode_tag(&self, name: &str, args: &[String], endian: Endian, encoding: Encoding) -> Result<Vec<u8>, String> {
+        let def = self.escapes.iter().find(|def| def.tag == name && def.arity() == args.len())
+            .ok_or_else(|| format!("[{name}] with {} arguments isn't an escape of {}", args.len(), self.name))?;
+        let mut body = vec![];
+        let mut args = args.iter();
+        for field in &def.fields {
+            match field {
+                Field::Fixed { size, value } => body.extend(uint_bytes(*value, *size, endian).unwrap_or_default()),
+                Field::Number { size, table } => {
+                    let arg = args.next().map(String::as_str).unwrap_or_default();
+                    let value = self.value(table.as_deref(), arg)
+                        .ok_or_else(|| format!("unknown value `{arg}` in [{name}]"))?;
+                    body.extend(uint_bytes(value, *size, endian)
+                        .ok_or_else(|| format!("value `{arg}` out of range in [{name}]"))?);
+                },
+                Field::Text => {
+                    let text = args.next().map(String::as_str).unwrap_or_default();
+                    body.extend(encoding.encode(text, endian)
+                        .ok_or_else(|| format!("`{text}` in [{name}] can't be written in {encoding:?}"))?);
+                }
+            }
+        }
+        let header = encoding.unit() + 2;
+        if body.len() > u8::MAX as usize - header {
+            return Err(format!("[{name}] is too long for an escape"));
+        }
+        let mut result = vec![];
+        encoding.push_unit(&mut result, markup::ESCAPE, endian);
+        result.push((header + body.len()) as u8);
+        result.push(def.ty);
+        result.extend(body);
+        Ok(result)
+    }
+    fn parse_tag(&self, tag: &str, position: usize) -> Result<Option<TextSegment>, MarkupError> {
+        let (name, arg) = match tag.split_once(':') {
+            Some((name, arg)) => (name, Some(arg)),
+            None => (tag, None)
+        };
+        if name == "raw" {
+            return markup::parse_raw(tag, position);
+        }
+        let mut error = None;
+        for def in self.escapes.iter().filter(|def| def.tag == name) {
+            let args = match (arg, def.arity()) {
+                (None, 0) => vec![],
+                (Some(arg), arity) if arity > 0 => arg.splitn(arity, ',').map(str::to_string).collect(),
+                _ => continue
+            };
+            if args.len() != def.arity() {
+                continue;
+            }
+            match self.encode_tag(name, &args, Endian::Big, self.encoding) {
+                Ok(_) => return Ok(Some(self.typed(TextSegment::Tag { name: name.to_string(), args }))),
+                Err(reason) => error = Some(MarkupError { position, reason })
+            }
+        }
+        error.map_or(Ok(None), Err)
+    }
+}
+
+/// Writes the profile as a definition that [`Profile::from_definition`] reads back.
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[profile:{}][entrysize:{}][encoding:{}]", self.name, self.entrysize, self.encoding.name())?;
+        writeln!(f, "{}", if self.message_ids { "[messageids]" } else { "" })?;
+        if !self.attributes.is_empty() {
+            writeln!(f)?;
+        }
+        for attribute in &self.attributes {
+            write!(f, "[attribute:{}][offset:{}][size:{}]", attribute.name, attribute.offset, attribute.size)?;
+            match &attribute.table {
+                Some(table) => writeln!(f, "[table:{table}]")?,
+                None => writeln!(f)?
+            }
+        }
+        if !self.escapes.is_empty() {
+            writeln!(f)?;
+        }
+        for escape in &self.escapes {
+            let fields = escape.fields.iter().map(|field| match field {
+                Field::Fixed { size, value } => format!("u{}={value}", size * 8),
+                Field::Number { size, table: Some(table) } => format!("u{}:{table}", size * 8),
+                Field::Number { size, table: None } => format!("u{}", size * 8),
+                Field::Text => String::from("text")
+            }).collect::<Vec<_>>();
+            writeln!(f, "[escape:{}][type:{}][fields:{}]", escape.tag, escape.ty, fields.join(","))?;
+        }
+        if !self.conditions.is_empty() {
+            writeln!(f)?;
+        }
+        for condition in &self.conditions {
+            write!(f, "[condition:{}][type:{}]", condition.name, condition.ty)?;
+            match &condition.choice {
+                Some(table) => writeln!(f, "[choice:{table}]")?,
+                None => writeln!(f)?
+            }
+        }
+        if !self.events.is_empty() {
+            writeln!(f)?;
+        }
+        for event in &self.events {
+            writeln!(f, "[event:{}][type:{}]", event.name, event.ty)?;
+        }
+        let mut tables = self.tables.iter().collect::<Vec<_>>();
+        tables.sort();
+        for (table, names) in tables {
+            writeln!(f)?;
+            for (value, name) in names {
+                writeln!(f, "[table:{table}][value:{value}][name:{name}]")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.parse::<u32>().is_ok() || name.contains([',', '[', ']']) {
+        return Err(format!("`{name}` can't be used as a name"));
+    }
+    Ok(())
+}
+
+fn parse_field(field: &str) -> Result<Field, String> {
+    if field == "text" {
+        return Ok(Field::Text);
+    }
+    let bad = || format!("bad field `{field}`, expected u8, u16 or u32 with `=value` or `:table`, or text");
+    let (ty, rest) = field.find(['=', ':']).map_or((field, ""), |i| field.split_at(i));
+    let size = match ty {
+        "u8" => 1,
+        "u16" => 2,
+        "u32" => 4,
+        _ => return Err(bad())
+    };
+    match rest.split_at_checked(1) {
+        None => Ok(Field::Number { size, table: None }),
+        Some(("=", value)) => {
+            let value = value.parse().map_err(|_| bad())?;
+            uint_bytes(value, size, Endian::Big).ok_or_else(|| format!("{value} doesn't fit {ty}"))?;
+            Ok(Field::Fixed { size, value })
+        },
+        Some((_, table)) if !table.is_empty() => Ok(Field::Number { size, table: Some(table.to_string()) }),
+        _ => Err(bad())
+    }
+}
+
+fn read_uint(bytes: &[u8], endian: Endian) -> u32 {
+    let mut value = [0; 4];
+    match endian {
+        Endian::Big => value[(4 - bytes.len())..].copy_from_slice(bytes),
+        Endian::Little => value[..bytes.len()].copy_from_slice(bytes)
+    }
+    match endian {
+        Endian::Big => u32::from_be_bytes(value),
+        Endian::Little => u32::from_le_bytes(value)
+    }
+}
+
+fn uint_bytes(value: u32, size: usize, endian: Endian) -> Option<Vec<u8>> {
+    if size < 4 && value >> (size * 8) != 0 {
+        return None;
+    }
+    Some(match endian {
+        Endian::Big => value.to_be_bytes()[(4 - size)..].to_vec(),
+        Endian::Little => value.to_le_bytes()[..size].to_vec()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bmg::tests::SAMPLE;
+    use crate::project;
+
+    const GALAXY: &str = "[bmg][magic:MESGbmg1][padding:02000000000000000000000000000000][profile:smg1]
+[section:INF1][entrysize:12][padding:0]
+[message:0][type:Talk][boxtype:Normal][sound:SeSvKinopioTalkHey][cam:Normal][cameraid:0][messageareaid:0][padding:0]
+Hello [waittime:30]Mario![newline][color:red]Red[color:black]
+[endmessage]
+[message:1][type:Shout][boxtype:Normal][sound:SeSvKinopioTalkHey][cam:Normal][cameraid:1][messageareaid:0][padding:0]
+Yes or no?
+[endmessage]
+[section:DAT1]
+";
+
+    fn decode(profile: &Profile, bytes: &[u8], encoding: Encoding) -> String {
+        let inf1 = INF1 { entries: vec![INF1Entry::default()], ..Default::default() };
+        let mut data = bytes.to_vec();
+        data.extend(vec![0; encoding.unit()]);
+        profile.get_string(&inf1, 0, &DAT1 { endian: Endian::Big, encoding, data }).unwrap()
+    }
+
+    #[test]
+    fn compile_and_render() {
+        let profile = Profile::default();
+        assert_eq!(profile.compile("A[newline]", Endian::Big, Encoding::Utf16).unwrap(), [0, b'A', 0, 0x1a, 6, 1, 0, 1]);
+        assert_eq!(profile.compile("[color:red]", Endian::Little, Encoding::Utf16).unwrap(), [0x1a, 0, 8, 0xff, 0, 0, 1, 0]);
+        for encoding in [Encoding::Utf16, Encoding::ShiftJis] {
+            for text in ["Hello [waittime:30]Mario![newline][color:red]Red[color:black][icon:star] [x] ♪",
+                "Bye [playername:formal][valint:3,1,2][sound:SE_SV_X][raw:01 00 05 00 02]", "[fontsize:7][icon:200]"] {
+                assert_eq!(decode(&profile, &profile.compile(text, Endian::Big, encoding).unwrap(), encoding), text);
+            }
+        }
+    }
+
+    #[test]
+    fn typed_segments() {
+        let profile = Profile::default();
+        assert_eq!(profile.parse("[color:red]A[newline][icon:200]").unwrap(),
+            [TextSegment::Color(1), TextSegment::Text(String::from("A")), TextSegment::NewLine, TextSegment::Icon(200)]);
+        assert_eq!(profile.render(&[TextSegment::Color(1), TextSegment::PlayerName(2)]), "[color:red][playername:moustache]");
+        assert_eq!(decode(&profile, &profile.encode(&[TextSegment::Sound(String::from("SE"))], Endian::Big, Encoding::Utf16).unwrap(),
+            Encoding::Utf16), "[sound:SE]");
+        // Wind Waker has its own color escape and no newline.
+        let ww = Profile::builtin("ww").unwrap();
+        assert_eq!(ww.parse("[color:3][furigana:1,a]").unwrap()[0], TextSegment::Color(3));
+        assert!(ww.encode(&[TextSegment::NewLine], Endian::Big, Encoding::Utf16).is_err());
+        // Mario Kart Wii colors are 16 bits wide and stay tags past 255.
+        let mkw = Profile::builtin("mkw").unwrap();
+        assert_eq!(mkw.parse("[color:300]").unwrap(), [TextSegment::Tag { name: String::from("color"), args: vec![String::from("300")] }]);
+    }
+
+    #[test]
+    fn escapes_that_dont_encode_back_stay_raw() {
+        let profile = Profile::default();
+        // A wait with a non-zero last byte and a newline with extra bytes.
+        for text in ["[raw:01 00 00 1e 05]", "[raw:01 00 01 00 00]", "[raw:09 00 00]"] {
+            assert_eq!(decode(&profile, &profile.compile(text, Endian::Big, Encoding::Utf16).unwrap(), Encoding::Utf16), text);
+        }
+    }
+
+    #[test]
+    fn bad_tags() {
+        let profile = Profile::default();
+        assert!(profile.compile("[color:nosuchcolor]", Endian::Big, Encoding::Utf16).is_err());
+        assert!(profile.compile("[waittime:300]", Endian::Big, Encoding::Utf16).is_err());
+        assert!(profile.compile("[raw:01]", Endian::Big, Encoding::Utf16).is_err());
+        assert_eq!(profile.parse("[unknown][newline:1]").unwrap(), [TextSegment::Text(String::from("[unknown][newline:1]"))]);
+    }
+
+    #[test]
+    fn attributes_round_trip() {
+        let bmg = BMG::from_bytes(&SAMPLE).unwrap();
+        assert_eq!(bmg.profile.name, "smg1");
+        let entry = &bmg.get_inf1().unwrap().entries[1];
+        let attributes = bmg.profile.read_attributes(&entry.attributes, Endian::Big).unwrap();
+        assert_eq!(attributes, [("type", String::from("Talk")), ("boxtype", String::from("SignBoard")),
+            ("sound", String::from("None")), ("cam", String::from("NoCam")), ("cameraid", String::from("1")),
+            ("messageareaid", String::from("2")), ("padding", String::from("0"))]);
+        let get = |name: &str| Ok(attributes.iter().find(|(n, _)| *n == name).unwrap().1.as_str());
+        assert_eq!(bmg.profile.write_attributes(get, Endian::Big).unwrap(), entry.attributes);
+        let mut little = entry.attributes.clone();
+        bmg.profile.swap_attributes(&mut little);
+        assert_eq!(bmg.profile.read_attributes(&little, Endian::Little).unwrap(), attributes);
+        assert!(bmg.profile.read_attributes(&entry.attributes[1..], Endian::Big).is_none());
+        let bad = |name: &str| Ok(if name == "cam" { "Upside" } else { "0" });
+        assert!(bmg.profile.write_attributes(bad, Endian::Big).is_err());
+    }
+
+    #[test]
+    fn typed_infos_match_the_profiles() {
+        let check = |entry: &INF1Entry, name: &str, endian: Endian, expected: &[(&str, &str)]| {
+            let profile = Profile::builtin(name).unwrap();
+            let attributes = profile.read_attributes(&entry.attributes, endian).unwrap();
+            let get = |name: &str| Ok(attributes.iter().find(|(n, _)| *n == name).unwrap().1.as_str());
+            for (name, value) in expected {
+                assert_eq!(get(name), Ok(*value), "{name}");
+            }
+            assert_eq!(profile.write_attributes(get, endian).unwrap(), entry.attributes);
+        };
+        let mut entry = INF1Entry::default();
+        let info = GalaxyInfo { cameraid: 0x102, soundid: 3, camtype: CameraType::NoCam, messagetype: MessageType::Shout,
+            messageboxtype: MessageBoxType::SignBoard, messageareaid: 5, padding: 6 };
+        entry.set_attributes(&info, Endian::Big).unwrap();
+        check(&entry, "smg1", Endian::Big, &[("cameraid", "258"), ("cam", "NoCam"), ("type", "Shout"),
+            ("boxtype", "SignBoard"), ("messageareaid", "5"), ("padding", "6")]);
+        let info = WindWakerInfo { messageid: 0x102, itemprice: 3, nextmessageid: 4, boxposition: 2, linesperbox: 0x405, unk4: 6, ..Default::default() };
+        entry.set_attributes(&info, Endian::Big).unwrap();
+        check(&entry, "ww", Endian::Big, &[("messageid", "258"), ("itemprice", "3"), ("nextmessageid", "4"),
+            ("boxposition", "bottom"), ("linesperbox", "1029"), ("unk4", "6")]);
+        let info = TwilightInfo { messageid: 7, drawtype: 1, unk3: 0x809, unk5: 10, ..Default::default() };
+        entry.set_attributes(&info, Endian::Little).unwrap();
+        check(&entry, "tp", Endian::Little, &[("messageid", "7"), ("drawtype", "bychar"), ("unk3", "2057"), ("unk5", "10")]);
+        let read: TwilightInfo = entry.get_attributes(Endian::Little).unwrap();
+        assert_eq!((read.messageid, read.unk3, read.unk5), (7, 0x809, 10));
+    }
+
+    #[test]
+    fn definitions() {
+        let profile = Profile::from_definition("# A test.
+[profile:test][entrysize:8][encoding:sjis][messageids]
+
+[attribute:kind][offset:0][size:2][table:kinds]
+[escape:pause][type:1][fields:u16=0,u8]
+[escape:name][type:2][fields:u16,text]
+[table:kinds][value:3][name:sign]
+[condition:Asks][type:2][choice:kinds]
+[event:Wave][type:7]
+").unwrap();
+        assert_eq!((profile.name.as_str(), profile.entrysize, profile.message_ids, profile.encoding), ("test", 8, true, Encoding::ShiftJis));
+        assert_eq!(profile.escapes[1].fields, [Field::Number { size: 2, table: None }, Field::Text]);
+        assert_eq!((profile.value(Some("kinds"), "Sign"), profile.value_name(Some("kinds"), 4)), (Some(3), String::from("4")));
+        assert_eq!((profile.condition_name(2), profile.condition_type("Asks"), profile.choices(2)), (String::from("Asks"), Some(2), Some("kinds")));
+        assert_eq!((profile.event_name(8), profile.event_type("Wave"), profile.event_type("9")), (String::from("8"), Some(7), Some(9)));
+        let error = |text: &str| Profile::from_definition(text).unwrap_err().line;
+        assert_eq!(error("[attribute:a][offset:0][size:1]"), 1);
+        assert_eq!(error("[profile:t][entrysize:8][encoding:utf16]\n[attribute:a][offset:3][size:2]"), 2);
+        assert_eq!(error("[profile:t][entrysize:8][encoding:utf16]\n[escape:e][type:1][fields:text,u8]"), 2);
+        assert_eq!(error("[profile:t][entrysize:8][encoding:utf16]\n\n[escape:e][type:1][fields:u8:nope]"), 3);
+        assert_eq!(error("[profile:t][entrysize:8][encoding:utf16]\n[event:a][type:1]\n[event:b][type:1]"), 3);
+        for name in Profile::BUILTIN {
+            assert_eq!(Profile::builtin(name).unwrap().name, name);
+        }
+    }
+
+    #[test]
+    fn detection_survives_reencoding() {
+        for encoding in [Encoding::Cp1252, Encoding::ShiftJis, Encoding::Utf8] {
+            let mut bmg = project::import(GALAXY).unwrap();
+            bmg.set_encoding(encoding).unwrap();
+            let bmg = BMG::from_bytes(&bmg.to_bytes().unwrap()).unwrap();
+            assert_eq!(bmg.profile.name, "smg1", "{encoding:?}");
+            assert_eq!(bmg.profile.get_string(bmg.get_inf1().unwrap(), 0, bmg.get_dat1().unwrap()).unwrap(),
+                "Hello [waittime:30]Mario![newline][color:red]Red[color:black]");
+        }
+    }
+}
@@ -0,0 +1,650 @@
+//! Plain text project format for a whole BMG.
+//!
+//! Every line outside of a message body is a list of `[key:value]` tags and
+//! the first tag says what the line describes:
+//!
+//! ```text
+//! [bmg][magic:MESGbmg1][padding:02000000000000000000000000000000][profile:smg1]
+//! [section:INF1][entrysize:12][padding:0]
+//! [message:0][type:Talk][boxtype:Normal][sound:None][cam:Normal][cameraid:0][messageareaid:0][padding:0]
+//! Hello[newline]world
+//! [endmessage]
+//! [message:1][type:Talk][boxtype:Normal][sound:None][cam:Normal][cameraid:0][messageareaid:0][padding:0][textof:0]
+//! [message:2][type:Talk][boxtype:Normal][sound:None][cam:Normal][cameraid:0][messageareaid:0][padding:0][hex]
+//! 0041d800
+//! [endmessage]
+//! [section:DAT1]
+//! [section:FLW1][padding:0]
+//! [node:0][type:text][unk:0][messageid:0][next:1][validity:0][unk2:0]
+//! [node:1][type:condition][unk:0][condition:MultipleChoice][arg:0][branch:0]
+//! [node:2][type:event][event:Emotion][branch:2][arg:3]
+//! [branches:2,65535,65535]
+//! [section:FLI1][entrylength:8][padding:0000000000]
+//! [flowentry:0][id:7][node:0][padding:0]
+//! [section:MID1][format:16][info:0][padding:0]
+//! [section:ZZZ1][data:00010000]
+//! ```
+//!
+//! What a message line holds after `[message:N]` depends on the game's
+//! [`Profile`], which the `[bmg]` line names and which defaults to `smg1`.
+//! Profiles that aren't built in are passed to [`import_with`].
+//! Entries that don't fit the profile keep their attributes as
+//! `[attributes:hex]`. When the file has a MID1 with an id for every message,
+//! the ids are written on the message lines as `[id:N]`, otherwise MID1 lists
+//! them itself as `[ids:N,..]`.
+//!
+//! Message bodies are text with the profile's escapes as tags and end at a
+//! line that only holds `[endmessage]`. `[textof:N]` shares the text of an earlier
+//! message and has no body, `[hex]` keeps text that can't be expressed as tags,
+//! such as unpaired surrogates or carriage returns, which line endings would
+//! lose, and text the profile can't decode. Unknown escapes are written as
+//! `[raw:..]`.
+//! Flow conditions and events are named by the profile as well, types it
+//! has no name for are written as numbers.
+//! Sections the tool doesn't know keep their contents as hex.
+//! DAT1 is rebuilt from the messages in order. When that wouldn't give back
+//! the file's DAT1, because its strings are laid out in another order or it
+//! holds bytes no message uses, the DAT1 line keeps the original as
+//! `[addresses:N,..][data:hex]`. It is used as long as every message still
+//! has its original text, any edit rebuilds DAT1 and drops the unused bytes.
+
+use crate::bmg::*;
+use crate::error::BmgError;
+use crate::profile::Profile;
+use std::fmt::{self, Write};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectError {
+    pub line: usize,
+    pub reason: String
+}
+
+impl fmt::Display for ProjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+impl std::error::Error for ProjectError {}
+
+pub fn export(bmg: &BMG) -> Result<String, BmgError> {
+    let mut result = String::new();
+    let magic = String::from_utf8_lossy(&bmg.header.magic);
+    writeln!(result, "[bmg][magic:{magic}][padding:{}][profile:{}]", to_hex(&bmg.header.padding), bmg.profile.name)?;
+    let endian = bmg.header.endian();
+    let ids = bmg.get_mid1().map(|mid1| &mid1.ids)
+        .filter(|ids| bmg.get_inf1().is_some_and(|inf1| inf1.entries.len() == ids.len()));
+    for section in &bmg.sections {
+        match section {
+            Section::INF1(inf1) => {
+                writeln!(result, "[section:INF1][entrysize:{}][padding:{}]", inf1.entrysize, inf1.padding)?;
+                let dat1 = bmg.get_dat1();
+                for (i, entry) in inf1.entries.iter().enumerate() {
+                    write!(result, "[message:{i}]")?;
+                    if let Some(ids) = ids {
+                        write!(result, "[id:{}]", ids[i])?;
+                    }
+                    match bmg.profile.read_attributes(&entry.attributes, endian) {
+                        Some(attributes) => for (name, value) in attributes {
+                            write!(result, "[{name}:{value}]")?;
+                        },
+                        None => write!(result, "[attributes:{}]", to_hex(&entry.attributes))?
+                    }
+                    write_message(&mut result, &bmg.profile, inf1, dat1, i, entry)?;
+                }
+            },
+            Section::DAT1(dat1) => match bmg.get_inf1() {
+                Some(inf1) if !rebuilds(inf1, dat1)? => {
+                    let addresses = inf1.entries.iter().map(|e| e.textaddress.to_string()).collect::<Vec<_>>().join(",");
+                    writeln!(result, "[section:DAT1][addresses:{addresses}][data:{}]", to_hex(&dat1.data))?;
+                },
+                _ => writeln!(result, "[section:DAT1]")?
+            },
+            Section::FLW1(flw1) => {
+                writeln!(result, "[section:FLW1][padding:{}]", flw1.padding)?;
+                for (i, node) in flw1.entries.iter().enumerate() {
+                    write_node(&mut result, &bmg.profile, i, node)?;
+                }
+                let branches = flw1.branch_nodes.iter()
+                    .map(|b| b.to_string()).collect::<Vec<_>>().join(",");
+                writeln!(result, "[branches:{branches}]")?;
+            },
+            Section::FLI1(fli1) => {
+                write!(result, "[section:FLI1][entrylength:{}][padding:{}]", fli1.entrylength, to_hex(&fli1.padding))?;
+                if !fli1.trailing.is_empty() {
+                    write!(result, "[trailing:{}]", to_hex(&fli1.trailing))?;
+                }
+                writeln!(result)?;
+                for (i, entry) in fli1.entries.iter().enumerate() {
+                    writeln!(result, "[flowentry:{i}][id:{}][node:{}][padding:{}]", entry.id, entry.node, entry.padding)?;
+                }
+            },
+            Section::MID1(mid1) => {
+                write!(result, "[section:MID1][format:{}][info:{}][padding:{}]", mid1.format, mid1.info, mid1.padding)?;
+                if ids.is_none() {
+                    let ids = mid1.ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(",");
+                    write!(result, "[ids:{ids}]")?;
+                }
+                if !mid1.trailing.is_empty() {
+                    write!(result, "[trailing:{}]", to_hex(&mid1.trailing))?;
+                }
+                writeln!(result)?;
+            },
+            Section::Unknown { data, .. } => {
+                writeln!(result, "[section:{}][data:{}]", section.name(), to_hex(data))?;
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn write_message(result: &mut String, profile: &Profile, inf1: &INF1, dat1: Option<&DAT1>, index: usize, entry: &INF1Entry) -> Result<(), BmgError> {
+    let shared = inf1.entries[..index].iter()
+        .position(|e| e.textaddress == entry.textaddress);
+    if let Some(shared) = shared {
+        return Ok(writeln!(result, "[textof:{shared}]")?);
+    }
+    let Some(dat1) = dat1 else {
+        return Ok(writeln!(result, "\n[endmessage]")?);
+    };
+    let bytes = dat1.get_bytes(entry.textaddress as usize).map_err(|e| e.in_message(index))?;
+    let text = profile.get_string(inf1, index, dat1).ok().filter(|text| {
+        profile.compile(text, dat1.endian, dat1.encoding).is_ok_and(|b| b == bytes)
+            && !text.contains('\r') && !text.split('\n').any(|line| line == "[endmessage]")
+    });
+    match text {
+        Some(text) if terminated(inf1, dat1, entry.textaddress as usize + bytes.len()) => {
+            writeln!(result, "\n{text}\n[endmessage]")?
+        },
+        _ => writeln!(result, "[hex]\n{}\n[endmessage]", to_hex(bytes))?
+    }
+    Ok(())
+}
+
+/// Whether the 0 at `end` really ends a string. With a byte encoding a 0
+/// that's followed by more text no message starts at is likely half of a
+/// UTF-16 unit, so the text read up to it isn't the whole message.
+fn terminated(inf1: &INF1, dat1: &DAT1, end: usize) -> bool {
+    dat1.encoding.unit() != 1 || match dat1.data.get(end + 1) {
+        None | Some(0) => true,
+        Some(_) => inf1.entries.iter().any(|entry| entry.textaddress as usize == end + 1)
+    }
+}
+
+/// Whether pushing the strings in message order gives back the same DAT1.
+fn rebuilds(inf1: &INF1, dat1: &DAT1) -> Result<bool, BmgError> {
+    let mut rebuilt = DAT1 { endian: dat1.endian, encoding: dat1.encoding, data: vec![] };
+    for (i, entry) in inf1.entries.iter().enumerate() {
+        let shared = inf1.entries[..i].iter().any(|e| e.textaddress == entry.textaddress);
+        if !shared && rebuilt.push_string(dat1.get_bytes(entry.textaddress as usize)?) != entry.textaddress {
+            return Ok(false);
+        }
+    }
+    let (used, rest) = dat1.data.split_at_checked(rebuilt.data.len()).unwrap_or((&dat1.data, &[]));
+    Ok(used == rebuilt.data && rest.iter().all(|&b| b == 0)
+        && (dat1.data.len() + 8).div_ceil(32) == (rebuilt.data.len() + 8).div_ceil(32))
+}
+
+fn write_node(result: &mut String, profile: &Profile, index: usize, node: &FLW1Entry) -> fmt::Result {
+    write!(result, "[node:{index}]")?;
+    match node {
+        FLW1Entry::Text(text) => writeln!(result, "[type:text][unk:{}][messageid:{}][next:{}][validity:{}][unk2:{}]",
+            text.unk, text.textid, text.nexttextid, text.validity, text.unk2),
+        FLW1Entry::Condition(con) => writeln!(result, "[type:condition][unk:{}][condition:{}][arg:{}][branch:{}]",
+            con.unk, profile.condition_name(con.conditiontype), con.arg, con.branchnodeid),
+        FLW1Entry::Event(eve) => writeln!(result, "[type:event][event:{}][branch:{}][arg:{}]",
+            profile.event_name(eve.event_type), eve.branchnodeid, eve.arg)
+    }
+}
+
+enum Text {
+    Markup(String),
+    Hex(String),
+    Shared(usize)
+}
+
+#[inline]
+pub fn import(project: &str) -> Result<BMG, ProjectError> {
+    import_with(project, None)
+}
+
+/// Imports with the given profile instead of the one the `[bmg]` line names.
+pub fn import_with(project: &str, profile: Option<Profile>) -> Result<BMG, ProjectError> {
+    let mut bmg = BMG::default();
+    let given = profile.is_some();
+    if let Some(profile) = profile {
+        bmg.profile = profile;
+    }
+    let mut messages = vec![];
+    let mut ids = vec![];
+    let mut original = None;
+    let mut lines = project.split('\n').enumerate()
+        .map(|(i, line)| (i + 1, line.strip_suffix('\r').unwrap_or(line)));
+    while let Some((number, line)) = lines.next() {
+        if line.is_empty() {
+            continue;
+        }
+        let error = |reason: String| ProjectError { line: number, reason };
+        let tags = Tags::parse(line).ok_or_else(|| error(format!("expected tags, found `{line}`")))?;
+        match tags.first() {
+            "bmg" => {
+                let magic = tags.get("magic").map_err(error)?.as_bytes();
+                bmg.header.magic = magic.try_into()
+                    .map_err(|_| error(String::from("magic must be 8 characters")))?;
+                bmg.header.padding = tags.hex("padding").map_err(error)?;
+                if tags.has("profile") && !given {
+                    let name = tags.get("profile").map_err(error)?;
+                    bmg.profile = Profile::builtin(name).ok_or_else(|| error(format!("unknown profile `{name}`")))?;
+                }
+            },
+            "section" => bmg.sections.push(match tags.get("section").map_err(error)? {
+                "INF1" => Section::INF1(INF1 {
+                    entrysize: tags.number("entrysize").map_err(error)?,
+                    padding: tags.number("padding").map_err(error)?,
+                    ..Default::default()
+                }),
+                "DAT1" => {
+                    if tags.has("addresses") {
+                        let addresses = tags.get("addresses").map_err(error)?;
+                        original = Some(addresses.split(',').filter(|a| !a.is_empty())
+                            .map(|a| a.trim().parse().map_err(|_| error(format!("bad address `{a}`"))))
+                            .collect::<Result<Vec<u32>, _>>()?);
+                    }
+                    let data = if tags.has("data") { tags.get("data").map_err(error)? } else { "" };
+                    Section::DAT1(DAT1 {
+                        data: from_hex(data).ok_or_else(|| error(String::from("bad hex data")))?,
+                        ..Default::default()
+                    })
+                },
+                "FLW1" => Section::FLW1(FLW1 {
+                    padding: tags.number("padding").map_err(error)?,
+                    ..Default::default()
+                }),
+                "FLI1" => {
+                    let trailing = if tags.has("trailing") { tags.get("trailing").map_err(error)? } else { "" };
+                    Section::FLI1(FLI1 {
+                        entrylength: tags.number("entrylength").map_err(error)?,
+                        padding: tags.hex("padding").map_err(error)?,
+                        trailing: from_hex(trailing).ok_or_else(|| error(format!("bad hex `{trailing}`")))?,
+                        ..Default::default()
+                    })
+                },
+                "MID1" => {
+                    let trailing = if tags.has("trailing") { tags.get("trailing").map_err(error)? } else { "" };
+                    let ids = if tags.has("ids") { tags.get("ids").map_err(error)? } else { "" };
+                    Section::MID1(MID1 {
+                        format: tags.number("format").map_err(error)?,
+                        info: tags.number("info").map_err(error)?,
+                        padding: tags.number("padding").map_err(error)?,
+                        ids: ids.split(',').filter(|id| !id.is_empty())
+                            .map(|id| id.trim().parse().map_err(|_| error(format!("bad id `{id}`"))))
+                            .collect::<Result<_, _>>()?,
+                        trailing: from_hex(trailing).ok_or_else(|| error(format!("bad hex `{trailing}`")))?,
+                        ..Default::default()
+                    })
+                },
+                other => {
+                    let magic = other.as_bytes().try_into()
+                        .map_err(|_| error(format!("section name `{other}` must be 4 characters")))?;
+                    let data = tags.get("data").map_err(error)?;
+                    Section::Unknown {
+                        magic: u32::from_le_bytes(magic),
+                        data: from_hex(data).ok_or_else(|| error(format!("bad hex `{data}`")))?
+                    }
+                }
+            }),
+            "message" => {
+                let Some(Section::INF1(inf1)) = bmg.sections.last_mut() else {
+                    return Err(error(String::from("message outside of INF1")));
+                };
+                let index: usize = tags.number("message").map_err(error)?;
+                if index != inf1.entries.len() {
+                    return Err(error(format!("expected message {}, found {index}", inf1.entries.len())));
+                }
+                let attributes = if tags.has("attributes") {
+                    let hex = tags.get("attributes").map_err(error)?;
+                    from_hex(hex).ok_or_else(|| error(format!("bad hex `{hex}`")))?
+                } else {
+                    bmg.profile.write_attributes(|name| tags.get(name), bmg.header.endian()).map_err(error)?
+                };
+                if attributes.len() + 4 != inf1.entrysize as usize {
+                    return Err(error(format!("attributes of {} bytes don't fit INF1 entries of {} bytes", attributes.len(), inf1.entrysize)));
+                }
+                inf1.entries.push(INF1Entry { textaddress: 0, attributes });
+                if tags.has("id") {
+                    ids.push(tags.number("id").map_err(error)?);
+                }
+                let text = if tags.has("textof") {
+                    let shared = tags.number("textof").map_err(error)?;
+                    if shared >= index {
+                        return Err(error(format!("textof must name an earlier message, found {shared}")));
+                    }
+                    Text::Shared(shared)
+                } else {
+                    let mut body = vec![];
+                    loop {
+                        match lines.next() {
+                            Some((_, "[endmessage]")) => break,
+                            Some((_, line)) => body.push(line),
+                            None => return Err(error(String::from("missing [endmessage]")))
+                        }
+                    }
+                    let body = body.join("\n");
+                    if tags.has("hex") { Text::Hex(body) } else { Text::Markup(body) }
+                };
+                messages.push((number, text));
+            },
+            "node" => {
+                let Some(Section::FLW1(flw1)) = bmg.sections.last_mut() else {
+                    return Err(error(String::from("node outside of FLW1")));
+                };
+                let index: usize = tags.number("node").map_err(error)?;
+                if index != flw1.entries.len() {
+                    return Err(error(format!("expected node {}, found {index}", flw1.entries.len())));
+                }
+                flw1.entries.push(read_node(&tags, &bmg.profile).map_err(error)?);
+            },
+            "flowentry" => {
+                let Some(Section::FLI1(fli1)) = bmg.sections.last_mut() else {
+                    return Err(error(String::from("flowentry outside of FLI1")));
+                };
+                let index: usize = tags.number("flowentry").map_err(error)?;
+                if index != fli1.entries.len() {
+                    return Err(error(format!("expected flow entry {}, found {index}", fli1.entries.len())));
+                }
+                fli1.entries.push(FLI1Entry {
+                    id: tags.number("id").map_err(error)?,
+                    node: tags.number("node").map_err(error)?,
+                    padding: tags.number("padding").map_err(error)?
+                });
+            },
+            "branches" => {
+                let Some(Section::FLW1(flw1)) = bmg.sections.last_mut() else {
+                    return Err(error(String::from("branches outside of FLW1")));
+                };
+                let branches = tags.get("branches").map_err(error)?;
+                flw1.branch_nodes = branches.split(',').filter(|b| !b.is_empty())
+                    .map(|b| b.trim().parse().map_err(|_| error(format!("bad branch `{b}`"))))
+                    .collect::<Result<_, _>>()?;
+            },
+            other => return Err(error(format!("unknown line `[{other}]`")))
+        }
+    }
+    if let Some(mid1) = bmg.get_mid1_mut() && mid1.ids.is_empty() {
+        mid1.ids = ids;
+    }
+    build_dat1(&mut bmg, messages, original)?;
+    Ok(bmg)
+}
+
+/// Rebuilds DAT1 from the messages, or keeps the `original` layout and the
+/// DAT1 read from the project if no message changed.
+fn build_dat1(bmg: &mut BMG, messages: Vec<(usize, Text)>, original: Option<Vec<u32>>) -> Result<(), ProjectError> {
+    let mut dat1 = DAT1 { endian: bmg.header.endian(), encoding: bmg.encoding(), ..Default::default() };
+    let data = bmg.get_dat1().map(|dat1| dat1.data.clone()).unwrap_or_default();
+    let original = original.map(|addresses| (addresses, DAT1 { data, ..dat1.clone() }));
+    let mut unchanged = original.as_ref().is_some_and(|(addresses, _)| addresses.len() == messages.len());
+    let mut addresses = Vec::with_capacity(messages.len());
+    for (i, (line, text)) in messages.into_iter().enumerate() {
+        let error = |reason: String| ProjectError { line, reason };
+        let bytes = match text {
+            Text::Markup(text) => bmg.profile.compile(&text, dat1.endian, dat1.encoding)
+                .map_err(|e| error(e.to_string()))?,
+            Text::Hex(hex) => from_hex(hex.trim()).ok_or_else(|| error(String::from("bad hex message")))?,
+            Text::Shared(index) => {
+                addresses.push(addresses[index]);
+                continue;
+            }
+        };
+        if let Some((original, data)) = original.as_ref().filter(|_| unchanged) {
+            unchanged = data.get_bytes(original[i] as usize).is_ok_and(|old| old == bytes);
+        }
+        addresses.push(dat1.push_string(&bytes));
+    }
+    if let Some((original, data)) = original.filter(|_| unchanged) {
+        (addresses, dat1.data) = (original, data.data);
+    }
+    if let Some(inf1) = bmg.get_inf1_mut() {
+        for (entry, address) in inf1.entries.iter_mut().zip(addresses) {
+            entry.textaddress = address;
+        }
+        inf1.entrynum = inf1.entries.len() as u16;
+    }
+    if let Some(flw1) = bmg.get_flw1_mut() {
+        flw1.nodenum = flw1.entries.len() as u16;
+        flw1.branchnodenum = flw1.branch_nodes.len() as u16;
+    }
+    if let Some(fli1) = bmg.get_fli1_mut() {
+        fli1.entrynum = fli1.entries.len() as u16;
+    }
+    if let Some(mid1) = bmg.get_mid1_mut() {
+        mid1.entrynum = mid1.ids.len() as u16;
+    }
+    if let Some(target) = bmg.get_dat1_mut() {
+        *target = dat1;
+    }
+    bmg.header.sectioncount = bmg.sections.len() as u32;
+    Ok(())
+}
+
+fn read_node(tags: &Tags, profile: &Profile) -> Result<FLW1Entry, String> {
+    match tags.get("type")? {
+        "text" => Ok(FLW1Entry::Text(EntryText {
+            unk: tags.number("unk")?,
+            textid: tags.number("messageid")?,
+            nexttextid: tags.number("next")?,
+            validity: tags.number("validity")?,
+            unk2: tags.number("unk2")?
+        })),
+        "condition" => {
+            let name = tags.get("condition")?;
+            let conditiontype = profile.condition_type(name)
+                .ok_or_else(|| format!("unknown condition `{name}`"))?;
+            Ok(FLW1Entry::Condition(EntryCondition {
+                unk: tags.number("unk")?,
+                conditiontype,
+                arg: tags.number("arg")?,
+                branchnodeid: tags.number("branch")?
+            }))
+        },
+        "event" => {
+            let name = tags.get("event")?;
+            let event_type = profile.event_type(name)
+                .ok_or_else(|| format!("unknown event `{name}`"))?;
+            Ok(FLW1Entry::Event(EntryEvent {
+                event_type,
+                branchnodeid: tags.number("branch")?,
+                arg: tags.number("arg")?
+            }))
+        },
+        other => Err(format!("unknown node type `{other}`"))
+    }
+}
+
+pub(crate) struct Tags<'a>(Vec<(&'a str, &'a str)>);
+
+impl<'a> Tags<'a> {
+    pub(crate) fn parse(mut line: &'a str) -> Option<Self> {
+        let mut tags = vec![];
+        while !line.is_empty() {
+            let end = line.find(']')?;
+            let tag = line.strip_prefix('[')?.get(..(end - 1))?;
+            tags.push(tag.split_once(':').unwrap_or((tag, "")));
+            line = &line[(end + 1)..];
+        }
+        if tags.is_empty() { None } else { Some(Self(tags)) }
+    }
+    pub(crate) fn first(&self) -> &'a str {
+        self.0[0].0
+    }
+    pub(crate) fn has(&self, key: &str) -> bool {
+        self.0.iter().any(|(k, _)| *k == key)
+    }
+    pub(crate) fn values(&self, key: &str) -> impl Iterator<Item = &'a str> {
+        self.0.iter().filter(move |(k, _)| *k == key).map(|(_, v)| *v)
+    }
+    pub(crate) fn get(&self, key: &str) -> Result<&'a str, String> {
+        self.0.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+            .ok_or_else(|| format!("missing [{key}:..]"))
+    }
+    pub(crate) fn number<T: std::str::FromStr>(&self, key: &str) -> Result<T, String> {
+        let value = self.get(key)?;
+        value.parse().map_err(|_| format!("bad number `{value}` in [{key}:..]"))
+    }
+    fn hex<const N: usize>(&self, key: &str) -> Result<[u8; N], String> {
+        let value = self.get(key)?;
+        from_hex(value).and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| format!("expected {N} hex bytes in [{key}:..]"))
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::with_capacity(bytes.len() * 2), |mut hex, b| {
+        let _ = write!(hex, "{b:02x}");
+        hex
+    })
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len()).step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..(i + 2))?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::Encoding;
+    use binrw::Endian;
+    use std::io::Cursor;
+
+    /// A Super Mario Galaxy project with every kind of message and a flow.
+    const SAMPLE: &str = "[bmg][magic:MESGbmg1][padding:02000000000000000000000000000000][profile:smg1]
+[section:INF1][entrysize:12][padding:0]
+[message:0][type:Talk][boxtype:Normal][sound:SeSvKinopioTalkHey][cam:Normal][cameraid:0][messageareaid:0][padding:0]
+Hello [waittime:30]Mario![newline][color:red]Red[color:black][icon:star] [x] ♪
+[endmessage]
+[message:1][type:Shout][boxtype:SignBoard][sound:None][cam:NoCam][cameraid:1][messageareaid:2][padding:0]
+Yes or no?
+[endmessage]
+[message:2][type:Talk][boxtype:Normal][sound:None][cam:Normal][cameraid:2][messageareaid:0][padding:0][textof:1]
+[message:3][type:Talk][boxtype:Normal][sound:None][cam:Normal][cameraid:3][messageareaid:0][padding:0]
+Bye [playername:formal][valint:3,1,2][sound:SE_SV_X]
+[endmessage]
+[message:4][type:Talk][boxtype:Normal][sound:None][cam:Normal][cameraid:4][messageareaid:0][padding:0][hex]
+0041d800
+[endmessage]
+[section:DAT1]
+[section:FLW1][padding:0]
+[node:0][type:text][unk:0][messageid:0][next:1][validity:0][unk2:0]
+[node:1][type:text][unk:0][messageid:1][next:2][validity:0][unk2:0]
+[node:2][type:condition][unk:0][condition:MultipleChoice][arg:0][branch:0]
+[node:3][type:text][unk:0][messageid:3][next:65535][validity:0][unk2:0]
+[node:4][type:event][event:Emotion][branch:2][arg:3]
+[node:5][type:text][unk:0][messageid:2][next:65535][validity:0][unk2:0]
+[branches:4,3,5]
+[section:FLI1][entrylength:8][padding:0000000000]
+[flowentry:0][id:7][node:0][padding:0]
+";
+
+    /// A Mario Kart Wii project, whose messages are also looked up by MID1 id.
+    const KART: &str = "[bmg][magic:MESGbmg1][padding:02000000000000000000000000000000][profile:mkw]
+[section:INF1][entrysize:8][padding:0]
+[message:0][id:1200][unk0:1][unk1:0][unk2:0][unk3:0]
+[scale:150]Lap [number:0,3]
+[endmessage]
+[message:1][id:1300][unk0:0][unk1:0][unk2:0][unk3:0]
+[color:2]Go!
+[endmessage]
+[section:DAT1]
+[section:MID1][format:16][info:0][padding:0]
+";
+
+    fn to_bytes(bmg: &BMG) -> Vec<u8> {
+        let mut writer = Cursor::new(Vec::new());
+        bmg.write(&mut writer).unwrap();
+        writer.into_inner()
+    }
+
+    fn reimport(bytes: &[u8]) -> Vec<u8> {
+        let project = export(&BMG::read(&mut Cursor::new(bytes)).unwrap()).unwrap();
+        to_bytes(&import(&project).unwrap())
+    }
+
+    #[test]
+    fn export_import_is_byte_identical() {
+        let bytes = to_bytes(&import(SAMPLE).unwrap());
+        assert_eq!(reimport(&bytes), bytes);
+        assert_eq!(export(&BMG::read(&mut Cursor::new(&bytes)).unwrap()).unwrap(), SAMPLE);
+    }
+
+    #[test]
+    fn import_rebuilds_dat1_in_message_order() {
+        let bmg = import(&SAMPLE.replace("Yes or no?", "Yes?")).unwrap();
+        let (inf1, dat1) = (bmg.get_inf1().unwrap(), bmg.get_dat1().unwrap());
+        assert_eq!(dat1.get_bytes(inf1.entries[1].textaddress as usize).unwrap(), bmg.profile.compile("Yes?", Endian::Big, Encoding::Utf16).unwrap());
+        assert_eq!(bmg.profile.get_string(inf1, 1, dat1).unwrap(), "Yes?");
+        assert_eq!(inf1.entries[2].textaddress, inf1.entries[1].textaddress);
+        assert_eq!(dat1.get_bytes(inf1.entries[4].textaddress as usize).unwrap(), [0, 0x41, 0xd8, 0]);
+        assert_eq!(import(&SAMPLE.replace("[color:red]", "[color:pink]")).unwrap_err().line, 3);
+    }
+
+    #[test]
+    fn keeps_dat1_it_cant_rebuild() {
+        let mut bmg = import(SAMPLE).unwrap();
+        bmg.get_dat1_mut().unwrap().data.extend([0xab, 0xcd]);
+        let bytes = to_bytes(&bmg);
+        assert_eq!(reimport(&bytes), bytes);
+        let project = export(&BMG::read(&mut Cursor::new(&bytes)).unwrap()).unwrap();
+        assert!(project.contains("[section:DAT1][addresses:"));
+        // Any edit rebuilds DAT1 without the unused bytes.
+        let edited = import(&project.replace("Yes or no?", "Yes?")).unwrap();
+        let dat1 = edited.get_dat1().unwrap();
+        assert!(!dat1.data.windows(2).any(|w| w == [0xab, 0xcd]));
+        assert_eq!(edited.profile.get_string(edited.get_inf1().unwrap(), 1, dat1).unwrap(), "Yes?");
+    }
+
+    #[test]
+    fn hex_keeps_what_lines_cant() {
+        let mut bmg = import(SAMPLE).unwrap();
+        let (inf1, dat1) = (bmg.get_inf1().unwrap(), bmg.get_dat1().unwrap());
+        let (first, second) = (inf1.entries[0].textaddress as usize, inf1.entries[1].textaddress as usize);
+        let mut data = dat1.data.clone();
+        // A carriage return in the first message and an escape too small to
+        // hold its type in the second.
+        data[first + 1] = b'\r';
+        data[second..(second + 4)].copy_from_slice(&[0, 0x1a, 2, 1]);
+        bmg.get_dat1_mut().unwrap().data = data;
+        let bytes = to_bytes(&bmg);
+        let project = export(&BMG::read(&mut Cursor::new(&bytes)).unwrap()).unwrap();
+        assert!(project.contains("[padding:0][hex]\n000d"));
+        assert!(project.contains("[padding:0][hex]\n001a0201"));
+        assert_eq!(to_bytes(&import(&project.replace('\n', "\r\n")).unwrap()), bytes);
+    }
+
+    #[test]
+    fn message_ids_round_trip() {
+        let bytes = to_bytes(&import(KART).unwrap());
+        assert_eq!(reimport(&bytes), bytes);
+        let bmg = BMG::read(&mut Cursor::new(&bytes)).unwrap();
+        assert_eq!(bmg.profile.name, "mkw");
+        assert_eq!(bmg.get_mid1().unwrap().ids, [1200, 1300]);
+        assert_eq!((bmg.find_message(1300), bmg.find_message(1400)), (Some(1), None));
+        assert_eq!(export(&bmg).unwrap(), KART);
+        // Without an id for every message MID1 lists them itself.
+        let listed = KART.replace("[id:1200]", "").replace("[id:1300]", "")
+            .replace("[info:0][padding:0]", "[info:0][padding:0][ids:5]");
+        let bmg = import(&listed).unwrap();
+        assert_eq!(bmg.find_message(5), Some(0));
+        assert_eq!(export(&bmg).unwrap(), listed);
+        // Galaxy 2 files add ids to the first game's layout.
+        let mut bmg = import(SAMPLE).unwrap();
+        bmg.sections.push(Section::MID1(MID1 { entrynum: 5, format: 16, ids: vec![10, 11, 12, 13, 14], ..Default::default() }));
+        let bytes = to_bytes(&bmg);
+        assert_eq!(BMG::read(&mut Cursor::new(&bytes)).unwrap().profile.name, "smg1");
+        assert_eq!(reimport(&bytes), bytes);
+    }
+}
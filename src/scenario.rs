@@ -0,0 +1,236 @@
+//! Scripted walks through conversation flows.
+//!
+//! A scenario file holds any number of scenarios, each starting with a
+//! `[scenario:..]` line that names it and says where the walk starts:
+//!
+//! ```text
+//! # Luigi gets the other greeting.
+//! [scenario:luigi greeting][message:120]
+//! [condition:PlayerLuigi][outcome:true]
+//! [choice:0]
+//! [expect:message][id:125]
+//! [expect:event][event:Emotion][arg:3]
+//! [expect:end]
+//! ```
+//!
+//! `[node:N]` starts at a flow node instead of a message. `[condition:..]`
+//! answers conditions of that type, `[choice:N]` answers the conditions the
+//! profile marks as choices with answer 0 or 1. Answers for the same condition are used in order and
+//! the last one keeps being used. A scenario passes when the walk shows every
+//! expected message and fires every expected event, in any order, and
+//! `[expect:end]` asks for the flow to finish. Blank lines and lines starting
+//! with `#` are ignored.
+
+use crate::bmg::*;
+use crate::flow::FlowWalker;
+use crate::profile::Profile;
+use crate::project::{ProjectError, Tags};
+use std::collections::HashMap;
+
+/// Walks longer than this are treated as stuck in a loop.
+pub const MAX_STEPS: usize = 10000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scenario {
+    pub name: String,
+    pub line: usize,
+    pub start: Start,
+    /// Outcomes by condition name, answers to choices are kept under [`Scenario::CHOICE`].
+    pub answers: Vec<(String, bool)>,
+    pub expects: Vec<Expect>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Start {
+    Message(u16),
+    Node(u16)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expect {
+    Message(u16),
+    Event { event: String, arg: Option<u32> },
+    End
+}
+
+pub fn parse(text: &str) -> Result<Vec<Scenario>, ProjectError> {
+    let mut result: Vec<Scenario> = vec![];
+    for (number, line) in text.lines().enumerate().map(|(i, line)| (i + 1, line.trim())) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |reason: String| ProjectError { line: number, reason };
+        let tags = Tags::parse(line).ok_or_else(|| error(format!("expected tags, found `{line}`")))?;
+        if tags.first() == "scenario" {
+            let start = if tags.has("node") {
+                Start::Node(tags.number("node").map_err(error)?)
+            } else {
+                Start::Message(tags.number("message").map_err(error)?)
+            };
+            result.push(Scenario {
+                name: tags.get("scenario").map_err(error)?.to_string(),
+                line: number,
+                start,
+                answers: vec![],
+                expects: vec![]
+            });
+            continue;
+        }
+        let scenario = result.last_mut()
+            .ok_or_else(|| error(String::from("expected a [scenario:..] line first")))?;
+        match tags.first() {
+            "condition" => {
+                let outcome = tags.number("outcome").map_err(error)?;
+                scenario.answers.push((tags.get("condition").map_err(error)?.to_string(), outcome));
+            },
+            "choice" => {
+                let outcome = match tags.get("choice").map_err(error)? {
+                    "0" => true,
+                    "1" => false,
+                    other => return Err(error(format!("choice must be 0 or 1, found `{other}`")))
+                };
+                scenario.answers.push((String::from(Scenario::CHOICE), outcome));
+            },
+            "expect" => scenario.expects.push(match tags.get("expect").map_err(error)? {
+                "message" => Expect::Message(tags.number("id").map_err(error)?),
+                "event" => Expect::Event {
+                    event: tags.get("event").map_err(error)?.to_string(),
+                    arg: if tags.has("arg") { Some(tags.number("arg").map_err(error)?) } else { None }
+                },
+                "end" => Expect::End,
+                other => return Err(error(format!("unknown expectation `{other}`")))
+            }),
+            other => return Err(error(format!("unknown line `[{other}]`")))
+        }
+    }
+    Ok(result)
+}
+
+impl Scenario {
+    /// The answer key of choices, brackets can't be part of a condition name.
+    pub const CHOICE: &str = "[choice]";
+    /// Walks the flow and returns everything that didn't go as expected.
+    /// Conditions and events are matched by their names in the profile.
+    pub fn run(&self, flw1: &FLW1, fli1: Option<&FLI1>, profile: &Profile) -> Vec<String> {
+        let mut failures = vec![];
+        let walker = match self.start {
+            Start::Message(message) => match FlowWalker::from_message(flw1, fli1, message) {
+                Ok(Some(walker)) => Ok(walker),
+                Ok(None) => return vec![format!("no flow starts at message {message}")],
+                Err(error) => Err(error)
+            },
+            Start::Node(node) => FlowWalker::new(flw1, node)
+        };
+        let mut walker = match walker {
+            Ok(walker) => walker,
+            Err(error) => return vec![error.to_string()]
+        };
+        let mut answers: HashMap<&str, Vec<bool>> = HashMap::new();
+        for (condition, outcome) in &self.answers {
+            answers.entry(condition).or_default().push(*outcome);
+        }
+        let mut asked: HashMap<&str, usize> = HashMap::new();
+        let mut messages = vec![];
+        let mut events = vec![];
+        let mut steps = 0;
+        while let Some(entry) = walker.entry() {
+            if steps == MAX_STEPS {
+                failures.push(format!("the flow didn't end after {MAX_STEPS} steps"));
+                break;
+            }
+            steps += 1;
+            let outcome = match entry {
+                FLW1Entry::Text(text) => {
+                    messages.push(text.textid);
+                    true
+                },
+                FLW1Entry::Condition(con) => {
+                    let name = profile.condition_name(con.conditiontype);
+                    let key = if profile.choices(con.conditiontype).is_some() { Self::CHOICE } else { name.as_str() };
+                    let Some((&key, outcomes)) = answers.get_key_value(key) else {
+                        failures.push(format!("condition {name} at node {} has no answer", walker.node().unwrap_or_default()));
+                        break;
+                    };
+                    let count = asked.entry(key).or_default();
+                    *count += 1;
+                    outcomes[(*count - 1).min(outcomes.len() - 1)]
+                },
+                FLW1Entry::Event(eve) => {
+                    events.push((profile.event_name(eve.event_type), eve.arg));
+                    true
+                }
+            };
+            if let Err(error) = walker.advance(outcome) {
+                failures.push(error.to_string());
+                break;
+            }
+        }
+        for expect in &self.expects {
+            match expect {
+                Expect::Message(id) if !messages.contains(id) => {
+                    failures.push(format!("never reached message {id}"));
+                },
+                Expect::Event { event, arg } if !events.iter().any(|(e, a)| e == event && arg.is_none_or(|arg| arg == *a)) => {
+                    match arg {
+                        Some(arg) => failures.push(format!("event {event} with arg {arg} never fired")),
+                        None => failures.push(format!("event {event} never fired"))
+                    }
+                },
+                Expect::End if walker.node().is_some() => failures.push(String::from("the flow didn't end")),
+                _ => {}
+            }
+        }
+        failures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flow::tests::sample;
+
+    const SCENARIOS: &str = "# Both answers of the question.
+[scenario:yes][message:0]
+[choice:0]
+[expect:message][id:3]
+[expect:event][event:Emotion][arg:3]
+[expect:end]
+
+[scenario:no][node:5]
+[choice:1]
+[expect:message][id:3]
+[expect:event][event:Emotion]
+";
+
+    #[test]
+    fn parse_scenarios() {
+        let scenarios = parse(SCENARIOS).unwrap();
+        assert_eq!(scenarios[0], Scenario {
+            name: String::from("yes"),
+            line: 2,
+            start: Start::Message(0),
+            answers: vec![(String::from(Scenario::CHOICE), true)],
+            expects: vec![
+                Expect::Message(3),
+                Expect::Event { event: String::from("Emotion"), arg: Some(3) },
+                Expect::End
+            ]
+        });
+        assert_eq!((scenarios[1].start, scenarios[1].line), (Start::Node(5), 8));
+        assert_eq!(parse("[choice:0]").unwrap_err().line, 1);
+        assert_eq!(parse("[scenario:x][message:0]\n[choice:2]").unwrap_err().line, 2);
+        assert!(parse("[scenario:x][message:0]\n[expect:nothing]").is_err());
+    }
+
+    #[test]
+    fn run_reports_regressions() {
+        let (flw1, profile) = (sample(), Profile::default());
+        let scenarios = parse(SCENARIOS).unwrap();
+        assert_eq!(scenarios[0].run(&flw1, None, &profile), Vec::<String>::new());
+        assert_eq!(scenarios[1].run(&flw1, None, &profile), ["never reached message 3", "event Emotion never fired"]);
+        let unanswered = parse("[scenario:x][message:0]\n[expect:end]").unwrap();
+        assert_eq!(unanswered[0].run(&flw1, None, &profile), ["condition MultipleChoice at node 3 has no answer", "the flow didn't end"]);
+        let nowhere = parse("[scenario:x][message:1]").unwrap();
+        assert_eq!(nowhere[0].run(&flw1, None, &profile), ["no flow starts at message 1"]);
+    }
+}
@@ -1,362 +1,387 @@
-use crate::font::*;
 use crate::bmg::*;
+use crate::error::BmgError;
+use crate::markup::{self, TextSegment};
+use crate::profile::Profile;
+use binrw::{BinRead, BinResult, BinWrite, Endian};
 use std::fmt::Write;
+use std::io::Cursor;
 
 
-impl INF1 {
-    pub fn get_string(&self, index: usize, dat1: &DAT1) -> String {
-        let address = self.entries[index].textaddress as usize;
-        let data = &dat1.data[address..];
-        let mut i = 0;
-        let mut string = vec![0u16; 0];
-        while i < data.len() {
-            let pair = &data[i..(i + 2)];
-            if pair == &[0, 0] {
-                break;
-            }
-            if pair[0] == 0x1A {
-                i += 1;
-                i = dat1.create_event_text(data, &mut string, i);
-            } 
-            else if pair == &[0x26,0x6A] {
-                string.push('\u{266A}' as u16);
-                i += 2;
-            }
-            else {
-                string.push(u16::from_ne_bytes(pair.try_into().unwrap_or_default()));
-                i += 2;
-            }
-        }
-        String::from_utf16_lossy(&string)
-    }
-}
-
 impl INF1Entry {
-    pub fn get_info(&self) -> String  {
-        let messagetype = self.messagetype;
-        let messageboxtype = self.messageboxtype;
-        let soundid = SoundId::as_string(self.soundid);
-        let camtype = self.camtype;
-        format!("[type:{messagetype:?}][boxtype:{messageboxtype:?}][sound:{soundid}][cam:{camtype:?}]")
+    /// Reads the attributes as a game's typed struct, like [`GalaxyInfo`].
+    pub fn get_attributes<T>(&self, endian: Endian) -> BinResult<T>
+    where T: BinRead, for<'a> T::Args<'a>: Default {
+        T::read_options(&mut Cursor::new(&self.attributes), endian, Default::default())
+    }
+    pub fn set_attributes<T>(&mut self, attributes: &T, endian: Endian) -> BinResult<()>
+    where T: BinWrite, for<'a> T::Args<'a>: Default {
+        let mut data = Cursor::new(vec![]);
+        attributes.write_options(&mut data, endian, Default::default())?;
+        self.attributes = data.into_inner();
+        Ok(())
     }
-    pub fn has_text(&self, dat1: &DAT1) -> bool {
+    pub fn has_text(&self, dat1: &DAT1) -> Result<bool, BmgError> {
         let addr = self.textaddress as usize;
-        let first = &dat1.data[addr..(addr+2)];
-        first != &[0, 0]
+        let first = dat1.data.get(addr..(addr + dat1.encoding.unit()))
+            .ok_or(BmgError::OutOfBounds { section: SectionMagic::DAT1, offset: addr, message: None })?;
+        Ok(first.iter().any(|&b| b != 0))
     }
     
 }
 
 impl DAT1 {
-    pub(self) fn create_event_text(&self, data: &[u8], string: &mut Vec<u16>, position: usize) -> usize {
-        let entrysize = data[position] as usize - 1;
-        let entrytype = data[position + 1];
-        let entryvalue = &data[(position+2)..(position+4)];
-        let entryvalue = u16::from_be_bytes(entryvalue.try_into().unwrap_or_default());
-        match entrytype {
-            1 => {
-                match entryvalue {
-                    0 => {
-                        let waittime = data[position+4];
-                        let mut waittime = format!("[waittime:{waittime}]").encode_utf16()
-                        .collect::<Vec<_>>();
-                        string.append(&mut waittime);
-                    },
-                    1 => {
-                        let mut newline = "[newline]".encode_utf16().collect::<Vec<_>>();
-                        string.append(&mut newline);
-                    },
-                    3 => {
-                        let mut center = "[center]".encode_utf16().collect::<Vec<_>>();
-                        string.append(&mut center);
-                    }
-                    _ => {}
-                }
-            },
-            2 => {
-                let string_size = entrysize - 5;
-                let mut sound = "[sound:".encode_utf16().collect::<Vec<_>>();
-                let mut sound_bytes = data[(position + 5)..(position + 5 + string_size)]
-                .chunks(2)
-                .map(|x| u16::from_ne_bytes(x.try_into().unwrap_or_default()))
-                .collect::<Vec<_>>();
-                sound.append(&mut sound_bytes);
-                sound.push(b']'.into());
-                string.append(&mut sound);
-            },
-            3 => {
-                let pic = PicIcon::new(entryvalue);
-                if let Some(pic) = pic {
-                    let mut pic = pic.as_utf16();
-                    string.append(&mut pic);
-                } else {
-                    let mut pic = format!("{entryvalue}").encode_utf16()
-                    .collect::<Vec<_>>();
-                    string.append(&mut pic);
-                }
-            },
-            4 => {
-                let mut size = match entryvalue {
-                    0 => String::from("[fontsize:small]"),
-                    1 => String::from("[fontsize:normal]"),
-                    2 => String::from("[fontsize:large]"),
-                    _ => format!("[fontsize:{entryvalue}]")
-                }.encode_utf16().collect::<Vec<_>>();
-                string.append(&mut size);
-            },
-            5 => {
-                if entryvalue == 0 {
-                    let nametype = data[position + 4];
-                    let mut nametype = match nametype {
-                        0 => String::from("[playername:normal]"),
-                        1 => String::from("[playername:formal]"),
-                        2 => String::from("[playername:moustache]"),
-                        _ => format!("[playername:{nametype}]")
-                    }.encode_utf16().collect::<Vec<_>>();
-                    string.append(&mut nametype);
+    #[inline]
+    pub fn read_u16(&self, bytes: &[u8]) -> u16 {
+        let bytes = bytes.try_into().unwrap_or_default();
+        match self.endian {
+            Endian::Big => u16::from_be_bytes(bytes),
+            Endian::Little => u16::from_le_bytes(bytes)
+        }
+    }
+    #[inline]
+    pub fn read_u32(&self, bytes: &[u8]) -> u32 {
+        let bytes = bytes.try_into().unwrap_or_default();
+        match self.endian {
+            Endian::Big => u32::from_be_bytes(bytes),
+            Endian::Little => u32::from_le_bytes(bytes)
+        }
+    }
+    pub fn set_endian(&mut self, endian: Endian, addresses: &[u32], profile: &Profile) -> Result<(), BmgError> {
+        if endian == self.endian {
+            return Ok(());
+        }
+        let (encoding, unit) = (self.encoding, self.encoding.unit());
+        let strings = addresses.iter()
+            .map(|&address| Ok((address as usize, self.get_bytes(address as usize)?.len())))
+            .collect::<Result<Vec<_>, BmgError>>()?;
+        // Strings can share their end with another one, every unit and escape
+        // is swapped the first time a string reaches it.
+        let mut swapped = vec![false; self.data.len()];
+        for (address, size) in strings {
+            let data = &mut self.data[address..(address + size)];
+            let mut i = 0;
+            while i + unit <= size && !std::mem::replace(&mut swapped[address + i], true) {
+                let is_escape = encoding.read_unit(&data[i..], self.endian) == Some(markup::ESCAPE);
+                data[i..(i + unit)].reverse();
+                if !is_escape || i + unit + 4 > size {
+                    i += unit;
+                    continue;
                 }
+                let entrysize = (data[i + unit] as usize).clamp(unit + 4, size - i);
+                let entrytype = data[i + unit + 1];
+                profile.swap_escape(entrytype, &mut data[(i + unit + 2)..(i + entrysize)], encoding);
+                i += entrysize;
+            }
+        }
+        self.endian = endian;
+        Ok(())
+    }
+    pub fn get_bytes(&self, address: usize) -> Result<&[u8], BmgError> {
+        let data = self.data.get(address..)
+            .ok_or(BmgError::OutOfBounds { section: SectionMagic::DAT1, offset: address, message: None })?;
+        let unit = self.encoding.unit();
+        let mut i = 0;
+        while i + unit <= data.len() {
+            match self.encoding.read_unit(&data[i..], self.endian) {
+                Some(0) => break,
+                Some(markup::ESCAPE) if i + unit < data.len() => i += (data[i + unit] as usize).max(unit),
+                _ => i += unit
             }
-            6 => {
-                let arg2 = &data[(position + 4)..(position + 8)];
-                let arg3 = &data[(position + 8)..(position + 12)];
-                let arg2 = u32::from_be_bytes(arg2.try_into().unwrap_or_default());
-                let arg3 = u32::from_be_bytes(arg3.try_into().unwrap_or_default());
-                let value = format!("[valint:{},{},{}]", entryvalue, arg2, arg3);
-                let mut value = value.encode_utf16().collect::<Vec<_>>();
-                string.append(&mut value);
-            },
-            7 => {
-                let arg2 = &data[(position + 4)..(position + 8)];
-                let arg3 = &data[(position + 8)..(position + 12)];
-                let arg2 = u32::from_be_bytes(arg2.try_into().unwrap_or_default());
-                let arg3 = u32::from_be_bytes(arg3.try_into().unwrap_or_default());
-                let value = format!("[valstr:{},{},{}]", entryvalue, arg2, arg3);
-                let mut value = value.encode_utf16().collect::<Vec<_>>();
-                string.append(&mut value);
-            },
-            255 => {
-                if entryvalue == 0 {
-                    let colorval = data[position + 4];
-                    let color = Color::new(colorval);
-                    if let Some(color) = color {
-                        let mut color = color.as_utf16();
-                        string.append(&mut color);
-                    } else {
-                        let mut color = format!("{colorval}").encode_utf16()
-                        .collect::<Vec<_>>();
-                        string.append(&mut color);
-                    }
-                }
-            },
-            _ => {}
         }
-        position + entrysize
+        Ok(&data[..i.min(data.len())])
+    }
+    /// Splits the string at `address` into segments, `read_escape` decodes the
+    /// escape at an offset and says where the next segment starts.
+    pub(crate) fn decode<F>(&self, address: usize, mut read_escape: F) -> Result<Vec<TextSegment>, BmgError>
+    where F: FnMut(usize) -> Result<(TextSegment, usize), BmgError> {
+        let mut result = vec![];
+        let mut text = address;
+        let mut i = address;
+        loop {
+            let unit = self.data.get(i..).and_then(|data| self.encoding.read_unit(data, self.endian))
+                .ok_or(BmgError::OutOfBounds { section: SectionMagic::DAT1, offset: i, message: None })?;
+            if unit == 0 {
+                break;
+            }
+            if unit != markup::ESCAPE {
+                i += self.encoding.unit();
+                continue;
+            }
+            if text < i {
+                result.push(TextSegment::Text(self.encoding.decode(&self.data[text..i], self.endian)));
+            }
+            let (segment, next) = read_escape(i)?;
+            result.push(segment);
+            i = next;
+            text = next;
+        }
+        if text < i {
+            result.push(TextSegment::Text(self.encoding.decode(&self.data[text..i], self.endian)));
+        }
+        Ok(result)
+    }
+    /// The whole escape starting at `start`, checked against its size byte.
+    pub(crate) fn get_escape(&self, start: usize) -> Result<&[u8], BmgError> {
+        let bad_escape = |reason: String| BmgError::BadEscape { offset: start, message: None, reason };
+        let unit = self.encoding.unit();
+        let header = self.data.get(start..(start + unit + 4))
+            .ok_or(BmgError::OutOfBounds { section: SectionMagic::DAT1, offset: start, message: None })?;
+        let entrysize = header[unit] as usize;
+        if entrysize < unit + 4 {
+            return Err(bad_escape(format!("size {entrysize} is too small")));
+        }
+        self.data.get(start..(start + entrysize))
+            .ok_or_else(|| bad_escape(format!("size {entrysize} runs past the end of DAT1")))
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-#[repr(u16)]
-pub enum MultipleChoice {
-    PenguinRace, 
-    SwimmingSchool, 
-    PenguinRaceAlt, 
-    BombTimeAttackLv1, 
-    PhantomTeresaRacer, 
-    BombTimeAttackLv2, 
-    TrialSurfingCoach, 
-    TrialSurfingHowTo, 
-    DeathPromenadeTeresaRacer, 
-    RosettaFinalBattle, 
-    CometTico, 
-    TransformTico, 
-    ChallengeSurfingCoach, 
-    TicoShopExchange, 
-    TicoShopWhich, 
-    KinopioPurple, 
-    CometTicoTell, 
-    TrialTamakoroHowTo, 
-    KnockOnTheDoor, 
-    LedPattern
+/// What a condition node checks, with its argument. The type is decoded by
+/// the name the profile gives it, the argument of `MultipleChoice` is named by
+/// the profile's choice table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Condition {
+    MultipleChoice(u16),
+    Coded(u16),
+    PlayerNearNpc(u16),
+    SwA(u16),
+    SwB(u16),
+    PlayerStateNoPowerUp(u16),
+    PlayerStateBee(u16),
+    PlayerStateBoo(u16),
+    PowerStarSpawned(u16),
+    AlreadyTalkedScene(u16),
+    PlayerLuigi(u16),
+    GetBranchAstroGalaxyResult(u16),
+    CutsceneActive(u16),
+    AlreadyTalkedSaved(u16),
+    IsMsgLedPattern(u16),
+    /// A condition type the profile doesn't name, or names differently, as
+    /// `(conditiontype, arg)`.
+    Unknown(u16, u16)
 }
 
-#[derive(Debug, Clone, Copy)]
-#[repr(u16)]
-pub enum ConditionType {
-    MultipleChoice,   
-    Coded,    
-    PlayerNearNpc,  
-    SwA,   
-    SwB,   
-    PlayerStateNoPowerUp,    
-    PlayerStateBee, 
-    PlayerStateBoo, 
-    PowerStarSpawned,   
-    AlreadyTalkedScene,  
-    PlayerLuigi,   
-    GetBranchAstroGalaxyResult,   
-    CutsceneActive,    
-    AlreadyTalkedSaved,    
-    IsMsgLedPattern
+impl Condition {
+    pub fn new(conditiontype: u16, arg: u16, profile: &Profile) -> Self {
+        match profile.conditions.iter().find(|c| c.ty == conditiontype).map(|c| c.name.as_str()) {
+            Some("MultipleChoice") => Self::MultipleChoice(arg),
+            Some("Coded") => Self::Coded(arg),
+            Some("PlayerNearNpc") => Self::PlayerNearNpc(arg),
+            Some("SwA") => Self::SwA(arg),
+            Some("SwB") => Self::SwB(arg),
+            Some("PlayerStateNoPowerUp") => Self::PlayerStateNoPowerUp(arg),
+            Some("PlayerStateBee") => Self::PlayerStateBee(arg),
+            Some("PlayerStateBoo") => Self::PlayerStateBoo(arg),
+            Some("PowerStarSpawned") => Self::PowerStarSpawned(arg),
+            Some("AlreadyTalkedScene") => Self::AlreadyTalkedScene(arg),
+            Some("PlayerLuigi") => Self::PlayerLuigi(arg),
+            Some("GetBranchAstroGalaxyResult") => Self::GetBranchAstroGalaxyResult(arg),
+            Some("CutsceneActive") => Self::CutsceneActive(arg),
+            Some("AlreadyTalkedSaved") => Self::AlreadyTalkedSaved(arg),
+            Some("IsMsgLedPattern") => Self::IsMsgLedPattern(arg),
+            _ => Self::Unknown(conditiontype, arg)
+        }
+    }
+    /// The `(conditiontype, arg)` pair stored in the file, `None` if the
+    /// profile has no condition of this name.
+    pub fn raw(self, profile: &Profile) -> Option<(u16, u16)> {
+        let (name, arg) = match self {
+            Self::MultipleChoice(arg) => ("MultipleChoice", arg),
+            Self::Coded(arg) => ("Coded", arg),
+            Self::PlayerNearNpc(arg) => ("PlayerNearNpc", arg),
+            Self::SwA(arg) => ("SwA", arg),
+            Self::SwB(arg) => ("SwB", arg),
+            Self::PlayerStateNoPowerUp(arg) => ("PlayerStateNoPowerUp", arg),
+            Self::PlayerStateBee(arg) => ("PlayerStateBee", arg),
+            Self::PlayerStateBoo(arg) => ("PlayerStateBoo", arg),
+            Self::PowerStarSpawned(arg) => ("PowerStarSpawned", arg),
+            Self::AlreadyTalkedScene(arg) => ("AlreadyTalkedScene", arg),
+            Self::PlayerLuigi(arg) => ("PlayerLuigi", arg),
+            Self::GetBranchAstroGalaxyResult(arg) => ("GetBranchAstroGalaxyResult", arg),
+            Self::CutsceneActive(arg) => ("CutsceneActive", arg),
+            Self::AlreadyTalkedSaved(arg) => ("AlreadyTalkedSaved", arg),
+            Self::IsMsgLedPattern(arg) => ("IsMsgLedPattern", arg),
+            Self::Unknown(conditiontype, arg) => return Some((conditiontype, arg))
+        };
+        Some((profile.conditions.iter().find(|c| c.name == name)?.ty, arg))
+    }
 }
 
-impl EntryCondition {
-    pub fn get_choice(&self) -> String {
-        if self.arg <= 19 {
-            let choice: MultipleChoice = unsafe{std::mem::transmute(self.arg)};
-            format!("{choice:?}")
-        } else {
-            self.arg.to_string()
+/// What an event node does, with its argument. The type is decoded by the
+/// name the profile gives it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    NpcEvent(u32),
+    NpcEventAlt(u32),
+    NextText(u32),
+    Unk(u32),
+    Emotion(u32),
+    SwA(u32),
+    SwB(u32),
+    Metamorphosis(u32),
+    /// An event type the profile doesn't name, or names differently, as
+    /// `(event_type, arg)`.
+    Unknown(u8, u32)
+}
+
+impl Event {
+    pub fn new(event_type: u8, arg: u32, profile: &Profile) -> Self {
+        match profile.events.iter().find(|e| e.ty == event_type).map(|e| e.name.as_str()) {
+            Some("NpcEvent") => Self::NpcEvent(arg),
+            Some("NpcEventAlt") => Self::NpcEventAlt(arg),
+            Some("NextText") => Self::NextText(arg),
+            Some("Unk") => Self::Unk(arg),
+            Some("Emotion") => Self::Emotion(arg),
+            Some("SwA") => Self::SwA(arg),
+            Some("SwB") => Self::SwB(arg),
+            Some("Metamorphosis") => Self::Metamorphosis(arg),
+            _ => Self::Unknown(event_type, arg)
         }
     }
-    pub fn get_con_type(&self) -> String {
-        if self.conditiontype <= 14 {
-            let con : ConditionType = unsafe {std::mem::transmute(self.conditiontype)};
-            format!("{con:?}")
-        } else {
-            self.conditiontype.to_string()
-        }
+    /// The `(event_type, arg)` pair stored in the file, `None` if the profile
+    /// has no event of this name.
+    pub fn raw(self, profile: &Profile) -> Option<(u8, u32)> {
+        let (name, arg) = match self {
+            Self::NpcEvent(arg) => ("NpcEvent", arg),
+            Self::NpcEventAlt(arg) => ("NpcEventAlt", arg),
+            Self::NextText(arg) => ("NextText", arg),
+            Self::Unk(arg) => ("Unk", arg),
+            Self::Emotion(arg) => ("Emotion", arg),
+            Self::SwA(arg) => ("SwA", arg),
+            Self::SwB(arg) => ("SwB", arg),
+            Self::Metamorphosis(arg) => ("Metamorphosis", arg),
+            Self::Unknown(event_type, arg) => return Some((event_type, arg))
+        };
+        Some((profile.events.iter().find(|e| e.name == name)?.ty, arg))
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-#[repr(u8)]
-pub enum EventType {
-    NpcEvent, 
-    NpcEventAlt, 
-    NextText, 
-    Unk, 
-    Emotion, 
-    SwA, 
-    SwB, 
-    Metamorphosis
+impl EntryCondition {
+    #[inline]
+    pub fn condition(&self, profile: &Profile) -> Condition {
+        Condition::new(self.conditiontype, self.arg, profile)
+    }
 }
 
 impl EntryEvent {
-    pub fn get_event_type(&self) -> String {
-        if self.event_type < 7 {
-            let event: EventType = unsafe{std::mem::transmute(self.event_type)};
-            format!("{event:?}")
-        } else {
-            self.event_type.to_string()
+    #[inline]
+    pub fn event(&self, profile: &Profile) -> Event {
+        Event::new(self.event_type, self.arg, profile)
+    }
+}
+
+impl FLW1Entry {
+    #[inline]
+    pub fn condition(&self, profile: &Profile) -> Option<Condition> {
+        match self {
+            Self::Condition(con) => Some(con.condition(profile)),
+            _ => None
+        }
+    }
+    #[inline]
+    pub fn event(&self, profile: &Profile) -> Option<Event> {
+        match self {
+            Self::Event(eve) => Some(eve.event(profile)),
+            _ => None
         }
     }
 }
 
 impl FLW1 {
-    pub fn has_flow(&self, id: u16) -> bool {
-        'outer: for i in 0..self.nodenum {
-            let flow = self.entries[i as usize];
-            if let FLW1Entry::Text(text) = flow {
-                if id == text.textid {
-                    for c in 0..self.nodenum {
-                        if c != i {
-                            let cflow = self.entries[c as usize];
-                            match cflow {
-                                FLW1Entry::Text(t) => {
-                                    if t.nexttextid == i {
-                                        continue 'outer;
-                                    }
-                                }
-                                FLW1Entry::Condition(con) => {
-                                    let branch = self.branch_nodes[con.branchnodeid as usize];
-                                    let next_branch = self.branch_nodes[con.branchnodeid as usize + 1];
-                                    if branch == i || next_branch == i {
-                                        continue 'outer;
-                                    }
-                                },
-                                FLW1Entry::Event(eve) => {
-                                    let branch = self.branch_nodes[eve.branchnodeid as usize];
-                                    if branch == i {
-                                        continue 'outer;
-                                    }
-                                }
-                            }
+    pub fn get_node(&self, from: u16, id: u16) -> Result<FLW1Entry, BmgError> {
+        self.entries.get(id as usize).copied()
+            .ok_or(BmgError::DanglingFlowRef { node: from, target: id })
+    }
+    pub fn get_branch(&self, from: u16, branch: usize) -> Result<u16, BmgError> {
+        self.branch_nodes.get(branch).copied()
+            .ok_or(BmgError::BadBranch { node: from, branch: branch as u16 })
+    }
+    /// Writes the nodes reachable from `id` depth first, skipping the ones
+    /// already `converted`. Long chains are walked with a stack of their own.
+    /// Nodes that `flows` start at get their FLI1 ids as `[flowid:..]`.
+    pub(crate) fn write_flow(&self, id: u16, node: FLW1Entry, flows: &[FLI1Entry], profile: &Profile, result: &mut String, converted: &mut [bool]) -> Result<(), BmgError> {
+        let target = |next: u16| if next == u16::MAX { String::from("none") } else { next.to_string() };
+        let mut stack = vec![(id, node)];
+        while let Some((id, node)) = stack.pop() {
+            if converted[id as usize] {
+                continue;
+            }
+            converted[id as usize] = true;
+            write!(result, "[node:{id}][type:")?;
+            let next = match node {
+                FLW1Entry::Text(text) => {
+                    write!(result, "text][messageid:{}][next:{}]", text.textid, target(text.nexttextid))?;
+                    for (key, value) in [("unk", text.unk), ("validity", text.validity), ("unk2", text.unk2)] {
+                        if value != 0 {
+                            write!(result, "[{key}:{value}]")?;
                         }
                     }
-                    return true;
+                    vec![text.nexttextid]
+                },
+                FLW1Entry::Condition(con) => {
+                    write!(result, "condition][condition:{}]", profile.condition_name(con.conditiontype))?;
+                    if let Some(choices) = profile.choices(con.conditiontype) {
+                        write!(result, "[choice:{}]", profile.value_name(Some(choices), con.arg as u32))?;
+                    } else {
+                        write!(result, "[arg:{}]", con.arg)?;
+                    }
+                    let true_id = self.get_branch(id, con.branchnodeid as usize)?;
+                    let false_id = self.get_branch(id, con.branchnodeid as usize + 1)?;
+                    write!(result, "[true:{}][false:{}]", target(true_id), target(false_id))?;
+                    if con.unk != 0 {
+                        write!(result, "[unk:{}]", con.unk)?;
+                    }
+                    vec![true_id, false_id]
+                },
+                FLW1Entry::Event(eve) => {
+                    let next = match eve.branchnodeid {
+                        u16::MAX => u16::MAX,
+                        branch => self.get_branch(id, branch as usize)?
+                    };
+                    write!(result, "event][event:{}][arg:{}][next:{}]", profile.event_name(eve.event_type), eve.arg, target(next))?;
+                    vec![next]
                 }
+            };
+            for flow in flows.iter().filter(|flow| flow.node == id) {
+                write!(result, "[flowid:{}]", flow.id)?;
             }
-        }
-        false
-    }
-    pub fn findnode(&self, id: u16, converted: &mut Vec<bool>) -> Result<String, std::fmt::Error> {
-        let mut result = String::new();
-        for i in 0..self.nodenum {
-            let node = self.entries[i as usize];
-            if let FLW1Entry::Text(text) = node {
-                if text.textid == id {
-                    self.write_flow(i, node, &mut result, converted)?;
-                    break;
+            writeln!(result)?;
+            for next in next.into_iter().rev() {
+                if next != u16::MAX {
+                    stack.push((next, self.get_node(id, next)?));
                 }
             }
         }
-        Ok(result)
+        Ok(())
     }
-    pub(self) fn write_flow(&self, id: u16, node: FLW1Entry, result: &mut String, converted: &mut Vec<bool>) -> std::fmt::Result {
-        if converted[id as usize] {
-            return Ok(());
-        }
-        converted[id as usize] = true;
-        write!(result, "[node:{id}][type:")?;
-        match node {
-            FLW1Entry::Text(text) => {
-                let mid = text.textid;
-                write!(result, "text][messageid:{mid}][next:")?;
-                if text.nexttextid == u16::MAX {
-                    writeln!(result, "none]")?;
-                } else {
-                    let next = text.nexttextid;
-                    writeln!(result, "{next}]")?;
-                    let next_node = self.entries[next as usize];
-                    self.write_flow(next, next_node, result, converted)?;
-                }
-            },
-            FLW1Entry::Condition(con) => {
-                write!(result, "condition][type:{}]", con.get_con_type())?;
-                if con.conditiontype == 0 {
-                    write!(result, "[choice:{}]", con.get_choice())?;
-                } else {
-                    write!(result, "[arg:{}]", con.arg)?;
-                }
-                write!(result, "[trueflow:")?;
-                let true_id = self.branch_nodes[con.branchnodeid as usize];
-                if true_id == u16::MAX {
-                    write!(result, "none]")?;
-                } else {
-                    write!(result, "{true_id}]")?;
-                }
-                write!(result, "[falseflow:")?;
-                let false_id = self.branch_nodes[con.branchnodeid as usize + 1];
-                if false_id == u16::MAX {
-                    write!(result, "none]")?;
-                } else {
-                    write!(result, "{false_id}]")?;
-                }
-                writeln!(result)?;
-                if (0..self.nodenum).contains(&true_id) {
-                    let true_node = self.entries[true_id as usize];
-                    self.write_flow(true_id, true_node, result, converted)?;
-                }
-                if (0..self.nodenum).contains(&false_id) {
-                    let false_node = self.entries[false_id as usize];
-                    self.write_flow(false_id, false_node, result, converted)?;
-                }
-            },
-            FLW1Entry::Event(eve) => {
-                write!(result, "event][type:{}]", eve.get_event_type())?;
-                write!(result, "[arg:{}][next:", eve.arg)?;
-                let next = eve.branchnodeid;
-                if next == u16::MAX {
-                    writeln!(result, "none]")?;
-                } else {
-                    writeln!(result, "{next}]")?;
-                }
-                if (0..self.nodenum).contains(&next) {
-                    let next_node = self.entries[next as usize];
-                    self.write_flow(next, next_node, result, converted)?;
-                }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_flow_nodes_convert_both_ways() {
+        let profile = Profile::default();
+        assert_eq!(Condition::new(0, 3, &profile), Condition::MultipleChoice(3));
+        assert_eq!(Condition::new(10, 1, &profile), Condition::PlayerLuigi(1));
+        assert_eq!(Condition::new(40, 2, &profile), Condition::Unknown(40, 2));
+        for conditiontype in 0..20 {
+            for arg in [0, 5, 19, 20, u16::MAX] {
+                assert_eq!(Condition::new(conditiontype, arg, &profile).raw(&profile), Some((conditiontype, arg)));
             }
         }
-        Ok(())
+        assert_eq!(Event::new(4, 3, &profile), Event::Emotion(3));
+        assert_eq!(Event::new(9, 1, &profile), Event::Unknown(9, 1));
+        for event_type in 0..12 {
+            assert_eq!(Event::new(event_type, 7, &profile).raw(&profile), Some((event_type, 7)));
+        }
+        let con = EntryCondition { conditiontype: 3, arg: 4, ..Default::default() };
+        assert_eq!(FLW1Entry::Condition(con).condition(&profile), Some(Condition::SwA(4)));
+        assert_eq!(FLW1Entry::Condition(con).event(&profile), None);
+        // Wind Waker names no flow nodes.
+        let ww = Profile::builtin("ww").unwrap();
+        assert_eq!(Condition::new(3, 4, &ww), Condition::Unknown(3, 4));
+        assert_eq!(Condition::SwA(4).raw(&ww), None);
+        assert_eq!(Event::Unknown(4, 3).raw(&ww), Some((4, 3)));
     }
 }
\ No newline at end of file